@@ -1,6 +1,6 @@
 defmodule Block.Extrinsic.AvailabilitySpecification do
   alias Block.Extrinsic.Guarantee.WorkReport
-  alias Util.{Collections, Hash, MerkleTree}
+  alias Util.{Hash, MerkleTree}
   import Codec.Encoder
 
   @type t :: %__MODULE__{
@@ -56,15 +56,28 @@ defmodule Block.Extrinsic.AvailabilitySpecification do
   end
 
   # Formula (14.17) v0.7.2 - u
+  # u = MB ([x ∣ x <− T[b♣,s♣]]), computed natively from the bundle and segment
+  # shards; b_clubs/1 and s_clubs/1 spell out the same steps
   @spec calculate_erasure_root(binary(), list(Types.export_segment())) :: Types.hash()
   def calculate_erasure_root(bundle_binary, exports) do
-    # u = MB ([x ∣ x <− T[b♣,s♣]])
-    MerkleTree.well_balanced_merkle_root(
-      for x <- Utils.transpose([b_clubs(bundle_binary), s_clubs(exports)]),
-          do: Collections.union_bin(x)
-    )
+    params = ErasureCoding.params()
+
+    bundle_shards =
+      ErasureCoding.encode(
+        Utils.pad_binary_right(bundle_binary, Constants.erasure_coded_piece_size()),
+        params
+      )
+
+    ErasureCoding.erasure_root(bundle_shards, segment_bundles(exports, params), params)
   end
 
+  # no exports are coded as a single zero segment, as in s_clubs/1
+  defp segment_bundles([], params),
+    do: segment_bundles([<<0::@export_segment_size*8>>], params)
+
+  defp segment_bundles(exports, params),
+    do: ErasureCoding.encode_segments(exports ++ WorkReport.paged_proofs(exports), params)
+
   def s_clubs([]), do: s_clubs([<<0::@export_segment_size*8>>])
 
   def s_clubs(exports) do
@@ -10,7 +10,7 @@ defmodule Block.Extrinsic.TicketProof do
   """
   alias Block.Extrinsic.TicketProof
   alias System.State.{EntropyPool, Safrole, SealKeyTicket}
-  alias Util.{Collections, Time}
+  alias Util.{Collections, Logger, Time}
   alias System.State.Validator
   use SelectiveMock
   import RangeMacros
@@ -93,16 +93,24 @@ defmodule Block.Extrinsic.TicketProof do
   @spec construct_n(list(t()), binary(), Types.bandersnatch_ring_root()) ::
           {:ok, list(SealKeyTicket.t())} | {:error, String.t()}
   mockable construct_n(ticket_proofs, eta2, epoch_root) do
-    Enum.reduce_while(ticket_proofs, {:ok, []}, fn %TicketProof{attempt: r} = ticket,
-                                                   {:ok, acc} ->
-      case proof_output(ticket, eta2, epoch_root) do
-        {:ok, output_hash} ->
-          {:cont, {:ok, acc ++ [%SealKeyTicket{id: output_hash, attempt: r}]}}
-
-        _ ->
-          {:halt, {:error, :bad_ticket_proof}}
-      end
-    end)
+    items =
+      for %TicketProof{attempt: r, signature: p} <- ticket_proofs,
+          do: {ticket_context(eta2, r), <<>>, p}
+
+    case RingVrf.ring_vrf_verify_batch(epoch_root, items) do
+      {:error, _} ->
+        {:error, :bad_ticket_proof}
+
+      results ->
+        Enum.zip(results, ticket_proofs)
+        |> Enum.reduce_while({:ok, []}, fn
+          {{:ok, output_hash}, %TicketProof{attempt: r}}, {:ok, acc} ->
+            {:cont, {:ok, acc ++ [%SealKeyTicket{id: output_hash, attempt: r}]}}
+
+          _, _ ->
+            {:halt, {:error, :bad_ticket_proof}}
+        end)
+    end
   end
 
   def create_proof([%Validator{} | _] = validators, entropy, keypair, prover_idx, attempt) do
@@ -113,17 +121,28 @@ defmodule Block.Extrinsic.TicketProof do
   def create_proof([k1 | _] = pub_keys, entropy, keypair, prover_idx, attempt)
       when is_binary(k1) do
     context = SigningContexts.jam_ticket_seal() <> entropy <> <<attempt>>
-    RingVrf.ring_vrf_sign(pub_keys, keypair, prover_idx, context, <<>>)
+
+    with {:ok, ring} <- RingVrf.padded_ring(pub_keys),
+         do: RingVrf.ring_vrf_sign(ring, keypair, prover_idx, context, <<>>)
   end
 
   def create_new_epoch_tickets(state, keypair, prover_idx) do
     keys = Enum.map(state.next_validators, & &1.bandersnatch)
 
     Task.async_stream(from_0_to(Constants.tickets_per_validator()), fn i ->
-      {p, _} = create_proof(keys, state.entropy_pool.n1, keypair, prover_idx, i)
-      %TicketProof{signature: p, attempt: i}
+      case create_proof(keys, state.entropy_pool.n1, keypair, prover_idx, i) do
+        {:error, _} = error -> error
+        {p, _} -> %TicketProof{signature: p, attempt: i}
+      end
+    end)
+    |> Enum.flat_map(fn
+      {:ok, %TicketProof{} = ticket} ->
+        [ticket]
+
+      {:ok, {:error, reason}} ->
+        Logger.warning("Cannot create ticket proof: #{inspect(reason)}")
+        []
     end)
-    |> Enum.map(fn {:ok, ticket} -> ticket end)
   end
 
   def tickets_for_new_block(existing_tickets, state, epoch_phase) do
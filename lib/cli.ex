@@ -18,7 +18,9 @@ defmodule Jamixir.CLI do
 
   # Main CLI function to generate keypair, encrypt, and store in structured format
   def generate_keypair(opts \\ []) do
-    {{private_key, _}, public_key} = RingVrf.generate_secret_from_rand()
+    # The native secret is an opaque handle, so draw the scalar here to be able to store it
+    private_key = :crypto.strong_rand_bytes(32)
+    {_, public_key} = RingVrf.generate_secret_from_scalar(:binary.bin_to_list(private_key))
 
     # Create keys directory if it doesn't exist
     File.mkdir_p!(@keys_dir)
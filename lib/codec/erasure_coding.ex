@@ -3,14 +3,179 @@ defmodule ErasureCoding do
     Application.get_env(:jamixir, :erasure_coding, __MODULE__).do_erasure_code(bin)
   end
 
+  @typedoc "`{original_shards, total_shards, symbol_size}` as understood by the NIF"
+  @type params :: {pos_integer(), pos_integer(), pos_integer()}
+
+  @symbol_size 2
+
+  @doc """
+  Erasure-coding parameters of the configured chainspec: any
+  `erasure_code_recovery_threshold` of the `validator_count` shards recover the data.
+  """
+  @spec params() :: params()
+  def params do
+    {Constants.erasure_code_recovery_threshold(), Constants.validator_count(), @symbol_size}
+  end
+
+  @doc """
+  Erasure-codes `size` bytes arriving as a stream of chunks, encoding rows as soon
+  as their data is complete instead of holding the whole input.
+  """
+  @spec encode_stream(Enumerable.t(), non_neg_integer(), params()) :: list(binary()) | error()
+  def encode_stream(chunks, size, params \\ params()) do
+    with encoder when is_reference(encoder) <- encoder_new(size, params),
+         :ok <- Enum.reduce_while(chunks, :ok, &push_chunk(encoder, &1, &2)) do
+      encoder_finish(encoder)
+    end
+  end
+
+  defp push_chunk(encoder, chunk, :ok) do
+    case encoder_push(encoder, chunk) do
+      :ok -> {:cont, :ok}
+      error -> {:halt, error}
+    end
+  end
+
+  @doc """
+  Decodes `size` bytes from `{index, shard}` pairs arriving as a stream, stopping
+  as soon as enough shards have arrived.
+  """
+  @spec decode_stream(Enumerable.t(), non_neg_integer(), params()) :: binary() | error()
+  def decode_stream(shards, size, params \\ params()) do
+    decoder = decoder_new(size, params)
+
+    Enum.reduce_while(shards, :too_few_shards, fn {index, shard}, _ ->
+      case decoder_add_shard(decoder, index, shard) do
+        true -> {:halt, :ready}
+        false -> {:cont, :too_few_shards}
+        error -> {:halt, error}
+      end
+    end)
+    |> case do
+      :ready -> decoder_decode(decoder)
+      error -> error
+    end
+  end
+
   @callback do_erasure_code(binary()) :: list(binary())
   def do_erasure_code(d) do
-    encode(d, Constants.core_count())
+    encode(d, params())
   end
 
   use Rustler, otp_app: :jamixir, crate: :erasure_coding
 
+  @typedoc "Returned instead of a result when shards or segments are malformed"
+  @type error ::
+          :invalid_params
+          | :mismatched_counts
+          | :too_few_shards
+          | :index_out_of_range
+          | :duplicate_index
+          | :wrong_shard_length
+          | :wrong_shard_count
+          | :wrong_segment_size
+          | :segment_out_of_range
+          | :too_much_data
+          | :incomplete_data
+          | :finished
+          | :error
+
   # coveralls-ignore-start
-  def encode(_bin, _c), do: :erlang.nif_error(:nif_not_loaded)
-  def decode(_shards, _indices, _size, _c), do: :erlang.nif_error(:nif_not_loaded)
+  def encode(_bin, _params), do: :erlang.nif_error(:nif_not_loaded)
+  def decode(_shards, _indices, _size, _params), do: :erlang.nif_error(:nif_not_loaded)
+
+  def encoder_new(_size, _params), do: :erlang.nif_error(:nif_not_loaded)
+  def encoder_push(_encoder, _chunk), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Returns, for every shard, the bytes that became final since the last drain, so
+  shards can be sent out while data is still arriving. `encoder_finish/1` then
+  returns only what was not drained.
+  """
+  @spec encoder_drain(reference()) :: list(binary()) | error()
+  def encoder_drain(_encoder), do: :erlang.nif_error(:nif_not_loaded)
+
+  def encoder_finish(_encoder), do: :erlang.nif_error(:nif_not_loaded)
+  def decoder_new(_size, _params), do: :erlang.nif_error(:nif_not_loaded)
+  def decoder_add_shard(_decoder, _index, _shard), do: :erlang.nif_error(:nif_not_loaded)
+  def decoder_decode(_decoder), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Sets how many threads `encode/2` and `decode/4` split rows over; 0 uses every worker.
+  The rows run on one shared pool of one worker per core, at most 4, built on first use,
+  so concurrent calls never take more threads than that between them. Both run on dirty
+  CPU schedulers either way, and only inputs with enough rows are split.
+  """
+  @spec set_threads(non_neg_integer()) :: :ok
+  def set_threads(_threads), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Erasure-codes exported segments, returning for each validator index the
+  concatenation of its shards of every segment, as served by CE 139/140.
+  """
+  @spec encode_segments(list(binary()), params()) :: list(binary()) | error()
+  def encode_segments(_segments, _params), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Rebuilds the segments at `segment_indices` from the shard bundles of the
+  validators at `validator_indices`.
+  """
+  @spec decode_segments(
+          list(binary()),
+          list(non_neg_integer()),
+          list(non_neg_integer()),
+          params()
+        ) :: list(binary()) | error()
+  def decode_segments(_bundles, _validator_indices, _segment_indices, _params),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Erasure root (GP 14.17) over the bundle shard and the segment shard bundle of
+  every validator, both indexed by validator.
+  """
+  @spec erasure_root(list(binary()), list(binary()), params()) :: Types.hash() | error()
+  def erasure_root(_bundle_shards, _segment_bundles, _params),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Like `erasure_root/3`, also returning the Merkle justification of each validator's shards.
+  """
+  @spec erasure_root_with_justifications(list(binary()), list(binary()), params()) ::
+          {Types.hash(), list(list(binary()))} | error()
+  def erasure_root_with_justifications(_bundle_shards, _segment_bundles, _params),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks the shards of validator `index` against an erasure root and justification.
+  """
+  @spec verify_shard(
+          Types.hash(),
+          non_neg_integer(),
+          binary(),
+          binary(),
+          list(binary()),
+          params()
+        ) :: boolean() | error()
+  def verify_shard(_root, _index, _bundle_shard, _segment_bundle, _justification, _params),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Rebuilds the shards at `wanted` (original or recovery) from the shards at `indexes`,
+  without decoding the whole data. `original_size` is the size of the encoded data.
+  """
+  @spec reconstruct_shards(
+          list(binary()),
+          list(non_neg_integer()),
+          list(non_neg_integer()),
+          non_neg_integer(),
+          params()
+        ) :: list(binary()) | error()
+  def reconstruct_shards(_shards, _indexes, _wanted, _original_size, _params),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Checks that a full shard set, in validator order, is a single valid codeword.
+  """
+  @spec verify_shards(list(binary()), params()) :: boolean() | error()
+  def verify_shards(_shards, _params), do: :erlang.nif_error(:nif_not_loaded)
 end
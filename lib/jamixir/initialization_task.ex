@@ -19,7 +19,7 @@ defmodule Jamixir.InitializationTask do
   def run do
     Log.info("🚀 Starting initialization task...")
 
-    case :persistent_term.get({RingVrf, :initialized}, false) do
+    case RingVrf.initialized?(Constants.validator_count()) do
       true ->
         Log.info("✅ RingVrf context already initialized")
 
@@ -96,6 +96,15 @@ defmodule KeyManager do
     end
   end
 
+  # Keep only the opaque native handle for the bandersnatch secret,
+  # so the raw scalar does not live on in the application env.
+  defp with_secret_handle(%{bandersnatch_priv: priv} = keys) when is_binary(priv) do
+    {{handle, _}, _} = RingVrf.generate_secret_from_scalar(:binary.bin_to_list(priv))
+    %{keys | bandersnatch_priv: handle}
+  end
+
+  defp with_secret_handle(keys), do: keys
+
   defp decrypt_private_key(encrypted_data, password) do
     <<iv::binary-size(16), ciphertext::binary>> = encrypted_data
     key = :crypto.hash(:sha256, password) |> binary_part(0, 32)
@@ -103,6 +112,7 @@ defmodule KeyManager do
   end
 
   def load_keys(%{bandersnatch: _, bandersnatch_priv: _} = keys) do
+    keys = with_secret_handle(keys)
     Application.put_env(:jamixir, :keys, keys)
     {:ok, keys}
   end
@@ -111,7 +121,7 @@ defmodule KeyManager do
 
   @spec load_keys(binary() | nil) :: {:error, any()} | {:ok, any()}
   def load_keys(keys_file) do
-    keys = JsonReader.read(keys_file) |> JsonDecoder.from_json()
+    keys = JsonReader.read(keys_file) |> JsonDecoder.from_json() |> with_secret_handle()
     # Store in application env
     Application.put_env(:jamixir, :keys, keys)
 
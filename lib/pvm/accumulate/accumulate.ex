@@ -31,17 +31,6 @@ defmodule PVM.Accumulate do
         %{n0_: n0_},
         opts \\ []
       ) do
-    # Get trace setting from environment variable
-    opts =
-      case System.get_env("PVM_TRACE") do
-        "true" ->
-          Keyword.put(opts, :trace, true)
-          |> Keyword.put(:trace_name, System.get_env("TRACE_NAME"))
-
-        _ ->
-          opts
-      end
-
     #  s = e except s_d[s]_b = e_d[s]_b + ∑ r_a (r∈x)
     update_path = [:services, service_index, :balance]
 
@@ -2,7 +2,7 @@ defmodule PVM.Accumulate.Executor do
   alias PVM.Accumulate.{Runner, Utils}
   require Logger
 
-  # high timeout to support PVM_TRACE
+  # high timeout to support traced and profiled runs
   @timeout 5_000
   def run(
         service_code,
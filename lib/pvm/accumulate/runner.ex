@@ -2,6 +2,7 @@ defmodule PVM.Accumulate.Runner do
   use GenServer
   import Pvm.Native
   alias Pvm.Native.ExecuteResult
+  alias PVM.Instrumentation
   require Logger
 
   defstruct [
@@ -13,7 +14,8 @@ defmodule PVM.Accumulate.Runner do
     :parent,
     :n0_,
     :timeslot,
-    :context_token
+    :context_token,
+    :instrumentation
   ]
 
   def start(
@@ -36,7 +38,7 @@ defmodule PVM.Accumulate.Runner do
   @impl true
   def init(
         {service_code, initial_context, encoded_args, gas, accumulation_inputs, n0_, timeslot,
-         parent, _opts}
+         parent, opts}
       ) do
     ctx_pair = {initial_context, initial_context}
 
@@ -49,7 +51,8 @@ defmodule PVM.Accumulate.Runner do
       parent: parent,
       n0_: n0_,
       timeslot: timeslot,
-      context_token: nil
+      context_token: nil,
+      instrumentation: Instrumentation.new(opts)
     }
 
     GenServer.cast(self(), :execute)
@@ -58,13 +61,17 @@ defmodule PVM.Accumulate.Runner do
 
   @impl true
   def handle_cast(:execute, %{service_code: sc, gas: g, encoded_args: a} = st) do
-    case execute(sc, 5, g, a) do
+    result = execute(sc, 5, g, a, st.instrumentation.options)
+    emit_program_cache_stats()
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: token} ->
         # VM paused on host call; wait for :ecall message
         {:noreply, %{st | context_token: token}}
 
       %ExecuteResult{output: output, used_gas: used_gas} ->
-        send(st.parent, {used_gas, output, st.ctx_pair})
+        finish(st, {used_gas, output, st.ctx_pair})
         {:stop, :normal, st}
     end
   end
@@ -105,7 +112,7 @@ defmodule PVM.Accumulate.Runner do
 
     case exit_reason do
       :out_of_gas ->
-        send(st.parent, {spent_gas, :out_of_gas, new_ctx_pair})
+        finish(st, {spent_gas, :out_of_gas, new_ctx_pair})
         {:stop, :normal, st}
 
       :halt ->
@@ -121,7 +128,7 @@ defmodule PVM.Accumulate.Runner do
               {spent_gas, <<>>, new_ctx_pair}
           end
 
-        send(st.parent, result)
+        finish(st, result)
         {:stop, :normal, st}
 
       :continue ->
@@ -137,7 +144,7 @@ defmodule PVM.Accumulate.Runner do
         {:noreply, %{st | ctx_pair: new_ctx_pair}}
 
       _ ->
-        send(st.parent, {spent_gas, :panic, st.ctx_pair})
+        finish(st, {spent_gas, :panic, st.ctx_pair})
         {:stop, :normal, st}
     end
   end
@@ -146,12 +153,15 @@ defmodule PVM.Accumulate.Runner do
   #  resuming the inner vm execution
   # if we hadn't done this, there would be a race condition where an next ecall message could of come in before the genserver state was updated
   def handle_info({:resume_vm, mem_ref, updated_state, context_token}, st) do
-    case resume(updated_state, mem_ref, context_token) do
+    result = resume(updated_state, mem_ref, context_token)
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: _token} ->
         {:noreply, st}
 
       %ExecuteResult{} = final ->
-        send(st.parent, {final.used_gas, final.output, st.ctx_pair})
+        finish(st, {final.used_gas, final.output, st.ctx_pair})
         {:stop, :normal, st}
     end
   end
@@ -160,4 +170,9 @@ defmodule PVM.Accumulate.Runner do
   def handle_info(_msg, st) do
     {:noreply, st}
   end
+
+  defp finish(st, result) do
+    Instrumentation.report(st.instrumentation, st.parent, :accumulate)
+    send(st.parent, result)
+  end
 end
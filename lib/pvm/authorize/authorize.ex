@@ -8,7 +8,8 @@ defmodule PVM.Authorize do
   import PVM.Constants.{HostCallId, HostCallResult}
   import PVM.Host.Gas
 
-  def execute(%WorkPackage{} = p, core_index, services) do
+  # opts take the Pvm.Native.execute/5 trace and profile options, see PVM.Instrumentation
+  def execute(%WorkPackage{} = p, core_index, services, opts \\ []) do
     p_u = WorkPackage.authorization_code(p, services)
 
     w_a = Constants.max_authorizer_code_size()
@@ -22,7 +23,7 @@ defmodule PVM.Authorize do
 
       _ ->
         args = e(t(core_index))
-        Executor.run(p_u, args, p)
+        Executor.run(p_u, args, p, opts)
     end
   end
 
@@ -2,6 +2,7 @@ defmodule PVM.Authorize.Runner do
   use GenServer
   import Pvm.Native
   alias Pvm.Native.ExecuteResult
+  alias PVM.Instrumentation
   require Logger
 
   defstruct [
@@ -9,7 +10,8 @@ defmodule PVM.Authorize.Runner do
     :encoded_args,
     :authorize_params,
     :parent,
-    :context_token
+    :context_token,
+    :instrumentation
   ]
 
   def start(service_code, encoded_args, authorize_params, opts \\ []) do
@@ -20,13 +22,14 @@ defmodule PVM.Authorize.Runner do
   end
 
   @impl true
-  def init({service_code, encoded_args, authorize_params, parent, _opts}) do
+  def init({service_code, encoded_args, authorize_params, parent, opts}) do
     state = %__MODULE__{
       service_code: service_code,
       encoded_args: encoded_args,
       authorize_params: authorize_params,
       parent: parent,
-      context_token: nil
+      context_token: nil,
+      instrumentation: Instrumentation.new(opts)
     }
 
     GenServer.cast(self(), :execute)
@@ -35,13 +38,17 @@ defmodule PVM.Authorize.Runner do
 
   @impl true
   def handle_cast(:execute, %{service_code: sc, encoded_args: a} = st) do
-    case execute(sc, 0, Constants.gas_is_authorized(), a) do
+    result = execute(sc, 0, Constants.gas_is_authorized(), a, st.instrumentation.options)
+    emit_program_cache_stats()
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: token} ->
         # VM paused on host call; wait for :ecall message
         {:noreply, %{st | context_token: token}}
 
       %ExecuteResult{output: output, used_gas: used_gas} ->
-        send(st.parent, {used_gas, output})
+        finish(st, {used_gas, output})
         {:stop, :normal, st}
     end
   end
@@ -67,7 +74,7 @@ defmodule PVM.Authorize.Runner do
 
     case exit_reason do
       :out_of_gas ->
-        send(st.parent, {spent_gas, :out_of_gas})
+        finish(st, {spent_gas, :out_of_gas})
         {:stop, :normal, st}
 
       :halt ->
@@ -83,7 +90,7 @@ defmodule PVM.Authorize.Runner do
               {spent_gas, <<>>}
           end
 
-        send(st.parent, result)
+        finish(st, result)
         {:stop, :normal, st}
 
       :continue ->
@@ -99,18 +106,21 @@ defmodule PVM.Authorize.Runner do
         {:noreply, st}
 
       _ ->
-        send(st.parent, {spent_gas, :panic})
+        finish(st, {spent_gas, :panic})
         {:stop, :normal, st}
     end
   end
 
   def handle_info({:resume_vm, mem_ref, updated_state, context_token}, st) do
-    case resume(updated_state, mem_ref, context_token) do
+    result = resume(updated_state, mem_ref, context_token)
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: _token} ->
         {:noreply, st}
 
       %ExecuteResult{} = final ->
-        send(st.parent, {final.used_gas, final.output})
+        finish(st, {final.used_gas, final.output})
         {:stop, :normal, st}
     end
   end
@@ -119,4 +129,9 @@ defmodule PVM.Authorize.Runner do
   def handle_info(_, st) do
     {:noreply, st}
   end
+
+  defp finish(st, result) do
+    Instrumentation.report(st.instrumentation, st.parent, :is_authorized)
+    send(st.parent, result)
+  end
 end
@@ -0,0 +1,39 @@
+defmodule PVM.Instrumentation do
+  @moduledoc """
+  Trace and profile of one invocation run by the accumulate, refine and authorize runners.
+
+  The runners take the `Pvm.Native.execute/5` options (`trace`, `max_steps`, `pc_range`,
+  `opcodes`, `profile`) in their opts and collect what every run of the invocation returns.
+  When anything was requested, the caller receives
+  `{:pvm_instrumentation, invocation, trace | nil, profile | nil}` right before the
+  invocation's result, a 4-tuple the executors never take for a result. Invocations
+  made through `PVM`, as block imports do, take these options from the
+  `:pvm_instrumentation` application env.
+  """
+  alias Pvm.Native.ExecuteResult
+
+  @options [:trace, :max_steps, :pc_range, :opcodes, :profile]
+
+  defstruct options: %{}, trace: nil, profile: nil
+
+  @type t :: %__MODULE__{options: map(), trace: binary() | nil, profile: map() | nil}
+
+  @spec new(keyword()) :: t()
+  def new(opts), do: %__MODULE__{options: opts |> Keyword.take(@options) |> Map.new()}
+
+  # the trace holds the records of each run in turn, the profile covers the invocation so far
+  @spec collect(t(), %ExecuteResult{}) :: t()
+  def collect(%__MODULE__{} = i, %ExecuteResult{trace: trace, profile: profile}) do
+    trace = if is_binary(trace), do: (i.trace || <<>>) <> trace, else: i.trace
+    %__MODULE__{i | trace: trace, profile: profile || i.profile}
+  end
+
+  @spec report(t(), pid(), atom()) :: :ok
+  def report(%__MODULE__{options: options} = i, parent, invocation) do
+    if options[:trace] == true or options[:profile] == true do
+      send(parent, {:pvm_instrumentation, invocation, i.trace, i.profile})
+    end
+
+    :ok
+  end
+end
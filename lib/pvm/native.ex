@@ -4,15 +4,52 @@ defmodule Pvm.Native do
 
   # VM execution entry point
   @spec execute(any(), any(), any(), any()) :: ExecuteResult.t()
-  def execute(_program, _pc, _gas, _args) do
+  def execute(program, pc, gas, args), do: execute(program, pc, gas, args, %{})
+
+  # Options: trace: true records every step (see Pvm.Native.Trace), narrowed by
+  # max_steps: n, pc_range: {from, to} and opcodes: [opcode]; each ExecuteResult
+  # of the invocation then carries the records of its run in :trace.
+  # profile: true collects gas per basic block, opcode and host call id, returned
+  # so far for the whole invocation in :profile (see Pvm.Native.Profile)
+  @spec execute(any(), any(), any(), any(), map()) :: ExecuteResult.t()
+  def execute(_program, _pc, _gas, _args, _options) do
     :erlang.nif_error(:nif_not_loaded)
   end
 
-  # Resume VM after handling a host call
+  # Resume VM after handling a host call; the context token is freed once the
+  # program finishes or the token is garbage collected, resuming it after that
+  # returns {:error, :no_vm_context}
   def resume(_state, _memory_ref, _context_token) do
     :erlang.nif_error(:nif_not_loaded)
   end
 
+  # Suspended programs whose context is still alive
+  def live_vm_contexts do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  # Cumulative %{hits, misses, entries} of the deblobbed program cache
+  def program_cache_stats do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  # Publishes the program cache counters as a [:jamixir, :pvm, :program_cache] telemetry event
+  def emit_program_cache_stats do
+    :telemetry.execute([:jamixir, :pvm, :program_cache], program_cache_stats(), %{})
+  end
+
+  # Versioned binary of a suspended invocation (state, memory and code hash),
+  # {:ok, snapshot} or {:error, :no_vm_context | :memory_not_available}
+  def snapshot_invocation(_state, _memory_ref, _context_token) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  # {:ok, {state, memory_ref, context_token}} to resume, or
+  # {:error, :invalid_snapshot | :invalid_program | :snapshot_mismatch}
+  def restore_invocation(_snapshot, _program) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
   def build_memory do
     :erlang.nif_error(:nif_not_loaded)
   end
@@ -68,6 +105,16 @@ defmodule Pvm.Native do
     :erlang.nif_error(:nif_not_loaded)
   end
 
+  # Versioned binary of a child VM (program, state and memory)
+  def snapshot_child_vm(_instance_ref) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  # {:ok, instance_ref} of a new child VM, or {:error, :invalid_snapshot | :invalid_program}
+  def restore_child_vm(_snapshot) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
   def set_child_vm_memory_access(_instance_ref, _page_index, _page_count, _permission) do
     :erlang.nif_error(:nif_not_loaded)
   end
@@ -87,7 +134,7 @@ defmodule Pvm.Native do
 end
 
 defmodule Pvm.Native.ExecuteResult do
-  defstruct [:used_gas, :output, :context_token]
+  defstruct [:used_gas, :output, :context_token, :trace, :profile]
 end
 
 defmodule Pvm.Native.VmState do
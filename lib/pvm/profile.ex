@@ -0,0 +1,19 @@
+defmodule Pvm.Native.Profile do
+  @moduledoc """
+  Gas profile returned in `Pvm.Native.ExecuteResult.profile` when executing with
+  `profile: true`. `blocks` maps the pc starting each basic block, `opcodes` each
+  opcode and `host_calls` each host call id to the gas spent on it. `folded` holds
+  one `entry;entry;... gas` line per call stack, frames named by the pc they were
+  entered at, as read by flamegraph tools.
+  """
+
+  @type t :: %{
+          blocks: %{non_neg_integer() => non_neg_integer()},
+          opcodes: %{non_neg_integer() => non_neg_integer()},
+          host_calls: %{non_neg_integer() => non_neg_integer()},
+          folded: String.t()
+        }
+
+  @spec write_folded(t(), Path.t()) :: :ok
+  def write_folded(%{folded: folded}, path), do: File.write!(path, folded)
+end
@@ -35,7 +35,7 @@ defmodule PVM do
   end
 
   def do_authorized(%WorkPackage{} = p, core_index, services),
-    do: PVM.Authorize.execute(p, core_index, services)
+    do: PVM.Authorize.execute(p, core_index, services, instrumentation())
 
   def refine(
         core,
@@ -80,7 +80,8 @@ defmodule PVM do
           import_segments,
           export_segment_offset,
           services,
-          preimages
+          preimages,
+          instrumentation()
         )
 
   @spec accumulate(
@@ -100,7 +101,12 @@ defmodule PVM do
       service_index,
       gas,
       accumulation_inputs,
-      %{n0_: n0_}
+      %{n0_: n0_},
+      instrumentation()
     )
   end
+
+  # block imports call the invocations without opts; tracing or profiling them is
+  # switched on with e.g. `config :jamixir, :pvm_instrumentation, trace: true`
+  defp instrumentation, do: Application.get_env(:jamixir, :pvm_instrumentation, [])
 end
@@ -29,7 +29,8 @@ defmodule PVM.Refine do
           list(list(binary())),
           non_neg_integer(),
           %{integer() => ServiceAccount.t()},
-          %{{Types.hash(), non_neg_integer()} => binary()}
+          %{{Types.hash(), non_neg_integer()} => binary()},
+          keyword()
         ) ::
           {binary() | WorkExecutionError.t(), list(binary()), Types.gas()}
   def execute(
@@ -40,7 +41,8 @@ defmodule PVM.Refine do
         import_segments,
         export_segment_offset,
         services,
-        extrinsics
+        extrinsics,
+        opts \\ []
       ) do
     work_item = Enum.at(work_package.work_items, work_item_index)
     %WorkItem{service: service_id, code_hash: wc, payload: wy, refine_gas_limit: wg} = work_item
@@ -66,7 +68,8 @@ defmodule PVM.Refine do
           extrinsics: extrinsics,
           services: services,
           service_id: service_id
-        }
+        },
+        opts
       )
     else
       {:error, :service_not_found} -> {:bad, [], 0}
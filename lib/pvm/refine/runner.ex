@@ -2,6 +2,7 @@ defmodule PVM.Refine.Runner do
   use GenServer
   import Pvm.Native
   alias Pvm.Native.ExecuteResult
+  alias PVM.Instrumentation
   require Logger
 
   defstruct [
@@ -11,7 +12,8 @@ defmodule PVM.Refine.Runner do
     :refine_context,
     :refine_params,
     :parent,
-    :context_token
+    :context_token,
+    :instrumentation
   ]
 
   def start(service_code, initial_context, encoded_args, gas, refine_params, opts \\ []) do
@@ -22,7 +24,7 @@ defmodule PVM.Refine.Runner do
   end
 
   @impl true
-  def init({service_code, refine_context, encoded_args, gas, refine_params, parent, _opts}) do
+  def init({service_code, refine_context, encoded_args, gas, refine_params, parent, opts}) do
     state = %__MODULE__{
       service_code: service_code,
       gas: gas,
@@ -30,7 +32,8 @@ defmodule PVM.Refine.Runner do
       refine_params: refine_params,
       refine_context: refine_context,
       parent: parent,
-      context_token: nil
+      context_token: nil,
+      instrumentation: Instrumentation.new(opts)
     }
 
     GenServer.cast(self(), :execute)
@@ -39,13 +42,17 @@ defmodule PVM.Refine.Runner do
 
   @impl true
   def handle_cast(:execute, %{service_code: sc, gas: g, encoded_args: a} = st) do
-    case execute(sc, 0, g, a) do
+    result = execute(sc, 0, g, a, st.instrumentation.options)
+    emit_program_cache_stats()
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: token} ->
         # VM paused on host call; wait for :ecall message
         {:noreply, %{st | context_token: token}}
 
       %ExecuteResult{output: output, used_gas: used_gas} ->
-        send(st.parent, {used_gas, output, st.refine_context})
+        finish(st, {used_gas, output, st.refine_context})
         {:stop, :normal, st}
     end
   end
@@ -72,7 +79,7 @@ defmodule PVM.Refine.Runner do
 
     case exit_reason do
       :out_of_gas ->
-        send(st.parent, {spent_gas, :out_of_gas, refine_context})
+        finish(st, {spent_gas, :out_of_gas, refine_context})
         {:stop, :normal, st}
 
       :halt ->
@@ -88,7 +95,7 @@ defmodule PVM.Refine.Runner do
               {spent_gas, <<>>, refine_context}
           end
 
-        send(st.parent, result)
+        finish(st, result)
         {:stop, :normal, st}
 
       :continue ->
@@ -104,18 +111,21 @@ defmodule PVM.Refine.Runner do
         {:noreply, %{st | refine_context: refine_context}}
 
       _ ->
-        send(st.parent, {spent_gas, :panic, st.refine_context})
+        finish(st, {spent_gas, :panic, st.refine_context})
         {:stop, :normal, st}
     end
   end
 
   def handle_info({:resume_vm, mem_ref, updated_state, context_token}, st) do
-    case resume(updated_state, mem_ref, context_token) do
+    result = resume(updated_state, mem_ref, context_token)
+    st = %{st | instrumentation: Instrumentation.collect(st.instrumentation, result)}
+
+    case result do
       %ExecuteResult{output: :waiting, context_token: _token} ->
         {:noreply, st}
 
       %ExecuteResult{} = final ->
-        send(st.parent, {final.used_gas, final.output, st.refine_context})
+        finish(st, {final.used_gas, final.output, st.refine_context})
         {:stop, :normal, st}
     end
   end
@@ -124,4 +134,9 @@ defmodule PVM.Refine.Runner do
   def handle_info(_, st) do
     {:noreply, st}
   end
+
+  defp finish(st, result) do
+    Instrumentation.report(st.instrumentation, st.parent, :refine)
+    send(st.parent, result)
+  end
 end
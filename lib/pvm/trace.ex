@@ -0,0 +1,31 @@
+defmodule Pvm.Native.Trace do
+  @moduledoc """
+  Step records returned in `Pvm.Native.ExecuteResult.trace` when executing with
+  `trace: true`. Each record is `pc::32, opcode::8, gas::64, changed::8` followed by
+  `changed` times `register::8, value::64`, all little endian; `gas` is what remained
+  before the instruction.
+  """
+
+  @type record :: %{
+          pc: non_neg_integer(),
+          opcode: non_neg_integer(),
+          gas: non_neg_integer(),
+          registers: list({non_neg_integer(), non_neg_integer()})
+        }
+
+  @spec decode(binary()) :: list(record())
+  def decode(trace), do: decode(trace, [])
+
+  defp decode(<<>>, records), do: Enum.reverse(records)
+
+  defp decode(
+         <<pc::32-little, opcode::8, gas::64-little, changed::8, rest::binary>>,
+         records
+       ) do
+    size = changed * 9
+    <<registers::binary-size(size), rest::binary>> = rest
+    registers = for <<index::8, value::64-little <- registers>>, do: {index, value}
+
+    decode(rest, [%{pc: pc, opcode: opcode, gas: gas, registers: registers} | records])
+  end
+end
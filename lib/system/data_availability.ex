@@ -4,7 +4,8 @@ defmodule System.DataAvailability do
   alias Jamixir.NodeStateServer
   alias Util.Logger
 
-  @callback do_get_segment(binary(), non_neg_integer()) :: binary()
+  @callback do_get_segment(binary(), non_neg_integer()) ::
+              binary() | {:error, ErasureCoding.error()}
   @callback do_get_justification(binary(), non_neg_integer()) :: binary()
 
   def get_segment(merkle_root, segment_index) do
@@ -53,7 +54,10 @@ defmodule System.DataAvailability do
           end
           |> Enum.unzip()
 
-        ErasureCoding.decode(shards, indexes, Constants.segment_size(), Constants.core_count())
+        case ErasureCoding.decode_segments(shards, indexes, [0], ErasureCoding.params()) do
+          [segment] -> segment
+          error -> {:error, error}
+        end
 
       segment ->
         segment
@@ -17,6 +17,7 @@ defmodule System.State.RotateKeys do
         ) ::
           {list(Validator.t()), list(Validator.t()), list(Validator.t()),
            Types.bandersnatch_ring_root()}
+          | {:error, {:invalid_public_key, binary()}}
 
   def rotate_keys(
         %Header{timeslot: timeslot_},
@@ -40,9 +41,10 @@ defmodule System.State.RotateKeys do
       # λ' = κ (current -> prev)
       prev_ = curr_validators
       # γ_Z' = z, z = O([kb ∣ k <- γk ])
-      epoch_root_ = RingVrf.cached_commitment(for p <- pending_, do: p.bandersnatch)
-
-      {pending_, current_, prev_, epoch_root_}
+      case RingVrf.cached_commitment(for p <- pending_, do: p.bandersnatch) do
+        {:error, _} = error -> error
+        epoch_root_ -> {pending_, current_, prev_, epoch_root_}
+      end
     else
       # Formula (6.13) v0.7.2 -  same epoch - no rotation
       # {γ_P', κ', λ', γ_z'} = {γ_P, κ, λ, γ_z}
@@ -13,14 +13,20 @@ defmodule Util.Crypto do
     Ed25519Zip215.batch_verify(items)
   end
 
+  # Raises on a malformed key, as :crypto.sign did; callers expect a signature
+  @spec sign(binary(), Ed25519Zip215.signing_key()) :: Ed25519Zip215.signature()
   def sign(payload, private_key) do
-    :crypto.sign(:eddsa, :none, payload, [private_key, :ed25519])
+    case Ed25519Zip215.sign(private_key, payload) do
+      {:error, reason} -> raise ArgumentError, "cannot sign with ed25519 key: #{reason}"
+      signature -> signature
+    end
   end
 
   def create_ed25519_key_pair(seed) do
     secret_seed = Hash.blake2b_256("jam_val_key_ed25519" <> seed)
+    {_handle, public_key} = Ed25519Zip215.keypair_from_seed(secret_seed)
 
-    :crypto.generate_key(:eddsa, :ed25519, secret_seed)
+    {public_key, secret_seed}
   end
 
   def create_bandersnatch_key_pair(seed) do
@@ -22,6 +22,41 @@ defmodule Util.Crypto.Ed25519Zip215 do
     :erlang.nif_error(:nif_not_loaded)
   end
 
+  @doc """
+  Like `batch_verify/1`, but when the batch fails each item is re-verified so the
+  caller gets the indices of the failing items together with their `verify/3` reason.
+  """
+  @spec batch_verify_detailed([{signature(), message(), public_key()}]) ::
+          :ok | {:error, [{non_neg_integer(), verify_result()}]}
+  def batch_verify_detailed(_items) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  @type signing_key :: reference() | binary()
+
+  @doc """
+  Derives an ed25519 key from a 32-byte seed, returning `{handle, public_key}`.
+  The handle keeps the secret in native memory and can be passed to `sign/2`.
+  """
+  @spec keypair_from_seed(binary()) ::
+          {reference(), public_key()} | {:error, :invalid_seed_length}
+  def keypair_from_seed(_seed) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  @doc """
+  Signs `message` with a key handle or a raw 32-byte seed.
+  """
+  @spec sign(signing_key(), message()) :: signature() | {:error, :invalid_seed_length}
+  def sign(_key, _message) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
+  @spec public_key(reference()) :: public_key()
+  def public_key(_key) do
+    :erlang.nif_error(:nif_not_loaded)
+  end
+
   @spec valid_signature?(signature(), message(), public_key()) :: boolean()
   def valid_signature?(signature, message, public_key) do
     verify(signature, message, public_key) == :ok
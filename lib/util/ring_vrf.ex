@@ -2,8 +2,7 @@ defmodule RingVrf do
   use Rustler, otp_app: :jamixir, crate: :bandersnatch_ring_vrf
   alias Util.Logger
 
-  @initialized_key {__MODULE__, :initialized}
-  @ring_size_key {__MODULE__, :ring_size}
+  @ring_sizes_key {__MODULE__, :ring_sizes}
 
   # load static ring context data from a file
   # following the example https://github.com/davxy/bandersnatch-vrfs-spec/blob/main/example/src/main.rs
@@ -11,77 +10,112 @@ defmodule RingVrf do
 
   def init_ring_context, do: init_ring_context(Constants.validator_count())
 
+  # Contexts are kept per ring size, so initializing another size (e.g. tiny and
+  # full chainspecs in one VM) is fine and re-initializing a size is a no-op.
   def init_ring_context(ring_size) do
-    case :persistent_term.get(@initialized_key, false) do
-      true ->
-        stored_size = :persistent_term.get(@ring_size_key)
+    if initialized?(ring_size) do
+      :ok
+    else
+      Logger.info("💍 Initializing ring context with size #{ring_size}")
 
-        # If already initialized with same size
-        if stored_size == ring_size do
+      case create_ring_context(ring_size) do
+        {} ->
           :ok
-        else
-          # Different ring size
-          raise """
-          RingVrf already initialized with ring_size=#{stored_size}.
-          Cannot reinitialize with ring_size=#{ring_size}.
-          """
-        end
-
-      false ->
-        Logger.info("💍 Initializing ring context with size #{ring_size}")
-
-        case create_ring_context(ring_size) do
-          {} ->
-            :ok
-
-          result ->
-            raise "Failed to initialize ring context: got unexpected result #{inspect(result)}"
-        end
-
-        # Store initialization state explicitly
-        :persistent_term.put(@initialized_key, true)
-        :persistent_term.put(@ring_size_key, ring_size)
-
-        :ok
-    end
-  end
 
-  def ring_size! do
-    case :persistent_term.get(@initialized_key, false) do
-      true ->
-        :persistent_term.get(@ring_size_key)
+        result ->
+          raise "Failed to initialize ring context: got unexpected result #{inspect(result)}"
+      end
+
+      ring_sizes = :persistent_term.get(@ring_sizes_key, MapSet.new())
+      :persistent_term.put(@ring_sizes_key, MapSet.put(ring_sizes, ring_size))
 
-      false ->
-        raise """
-        RingVrf not initialized — must be initialized during node startup.
-        """
+      :ok
     end
   end
 
+  def initialized?(ring_size),
+    do: MapSet.member?(:persistent_term.get(@ring_sizes_key, MapSet.new()), ring_size)
+
+  @null_key <<0::256>>
+
   # Formula (G.3) v0.7.2
-  @spec cached_commitment(any()) :: any()
+  @spec cached_commitment(list(binary())) :: binary() | {:error, {:invalid_public_key, binary()}}
   def cached_commitment(keys) do
-    create_commitment(keys)
+    with {:ok, ring} <- padded_ring(keys), do: create_commitment(ring)
   end
 
-  @spec create_commitment(any()) :: any()
-  def create_commitment(_keys), do: :erlang.nif_error(:nif_not_loaded)
+  # Ring NIFs reject invalid keys. Nullified offenders carry the null key, the
+  # only one the padding point stands in for; it becomes nil. Any other invalid
+  # key is returned as an error.
+  @spec padded_ring(list(binary())) ::
+          {:ok, list(binary() | nil)} | {:error, {:invalid_public_key, binary()}}
+  def padded_ring(keys) do
+    Enum.reduce_while(keys, {:ok, []}, fn
+      @null_key, {:ok, ring} ->
+        {:cont, {:ok, [nil | ring]}}
+
+      key, {:ok, ring} ->
+        if valid_public_key?(key),
+          do: {:cont, {:ok, [key | ring]}},
+          else: {:halt, {:error, {:invalid_public_key, key}}}
+    end)
+    |> case do
+      {:ok, ring} -> {:ok, Enum.reverse(ring)}
+      error -> error
+    end
+  end
+
+  def valid_public_key?(key), do: valid_public_key_impl(key)
+
+  defp valid_public_key_impl(_key), do: :erlang.nif_error(:nif_not_loaded)
+
+  def create_commitment(keys, ring_size \\ Constants.validator_count()),
+    do: create_commitment_impl(ring_size, keys)
+
+  defp create_commitment_impl(_ring_size, _keys), do: :erlang.nif_error(:nif_not_loaded)
 
   # Formula (G.4) v0.7.2
   # Formula (G.5) v0.7.2
-  def ring_vrf_verify(commitment, context, message, signature) do
-    ring_vrf_verify_impl(commitment, context, message, signature)
+  def ring_vrf_verify(
+        commitment,
+        context,
+        message,
+        signature,
+        ring_size \\ Constants.validator_count()
+      ) do
+    ring_vrf_verify_impl(ring_size, commitment, context, message, signature)
   end
 
-  defp ring_vrf_verify_impl(_commitment, _context, _message, _signature),
+  defp ring_vrf_verify_impl(_ring_size, _commitment, _context, _message, _signature),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # Verifies a list of {context, message, signature} against one commitment.
+  # Returns one {:ok, output_hash} | {:error, reason} per item, in order.
+  # Each proof is still verified on its own; only the verifier setup is shared.
+  def ring_vrf_verify_batch(commitment, items, ring_size \\ Constants.validator_count()),
+    do: ring_vrf_verify_batch_impl(ring_size, commitment, items)
+
+  defp ring_vrf_verify_batch_impl(_ring_size, _commitment, _items),
     do: :erlang.nif_error(:nif_not_loaded)
 
   # No explicit formula
   # this is the set of signatures F̄m∈Yr∈YR ⟨x ∈ Y⟩ ⊂ Y784
-  def ring_vrf_sign(_ring, _secret, _prover_idx, _context, _message),
+  def ring_vrf_sign(
+        ring,
+        secret,
+        prover_idx,
+        context,
+        message,
+        ring_size \\ Constants.validator_count()
+      ),
+      do: ring_vrf_sign_impl(ring_size, ring, secret, prover_idx, context, message)
+
+  defp ring_vrf_sign_impl(_ring_size, _ring, _secret, _prover_idx, _context, _message),
     do: :erlang.nif_error(:nif_not_loaded)
 
   # Function to handle (secret, public_key) pair generation
+  # Secrets live in a native resource that is zeroized when collected, the keypair
+  # returned is {secret_handle, public_key} and the scalar never reaches Elixir
   # Generate a secret from a seed
   # not explictly mentioned in the paper - but mentioned in https://eprint.iacr.org/2023/002
   # and of course, we cannot sign and verify without creating secret/public key pairs
@@ -114,8 +148,19 @@ defmodule RingVrf do
   def ietf_vrf_verify(_key, _context, _message, _signature),
     do: :erlang.nif_error(:nif_not_loaded)
 
-  def ietf_vrf_output(secret, context), do: ietf_vrf_sign(secret, context, <<>>) |> elem(1)
+  # Verifies a list of {key, context, message, signature}, e.g. seal and entropy
+  # signatures of many blocks during sync.
+  # Returns one {:ok, output_hash} | {:error, reason} per item, in order.
+  def ietf_vrf_verify_batch(_items), do: :erlang.nif_error(:nif_not_loaded)
+
+  def ietf_vrf_output(secret, context), do: vrf_output(secret, context)
+
+  # The ring output only depends on the secret and context, ring and index are
+  # kept for symmetry with ring_vrf_sign.
+  def ring_vrf_output(_ring, secret, _prover_idx, context), do: vrf_output(secret, context)
+
+  def vrf_output(_secret, _context), do: :erlang.nif_error(:nif_not_loaded)
 
-  def ring_vrf_output(ring, secret, prover_idx, context),
-    do: ring_vrf_sign(ring, secret, prover_idx, context, <<>>) |> elem(1)
+  # Output hash carried by a ring or IETF signature, without verifying it.
+  def vrf_signature_output(_signature), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -53,6 +53,7 @@ defmodule Jamixir.MixProject do
       {:memoize, "~> 1.4"},
       {:ex_keccak, "~> 0.7.8"},
       {:rustler, "~> 0.34.0"},
+      {:telemetry, "~> 1.3"},
       {:dotenv, "~> 3.1.0"},
       {:temp, "~> 0.4"},
       {:jamixir_vm, git: "git@github.com:jamixir/jamixir-vm.git", branch: "main"},
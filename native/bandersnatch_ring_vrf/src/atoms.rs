@@ -0,0 +1,25 @@
+rustler::atoms! {
+    ok,
+    error,
+
+    // verification errors
+    invalid_signature,
+    verification_failed,
+    hash_conversion_failed,
+
+    // input errors
+    invalid_vrf_input,
+    invalid_public_key,
+    invalid_commitment,
+
+    // ring context errors
+    ring_context_not_initialized,
+    srs_deserialization_failed,
+    ring_size_too_large,
+
+    // encoding errors
+    serialization_failed,
+    allocation_failed,
+
+    unknown_error
+}
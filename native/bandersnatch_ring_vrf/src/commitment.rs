@@ -1,17 +1,26 @@
-use crate::rustler_bridges::{public::PublicBridge, FixedColumnsCommittedBridge};
-use crate::{ring_context::ring_context, types::Bandersnatch as S};
-use rustler::NifResult;
+use crate::rustler_bridges::{FixedColumnsCommittedBridge, RingBridge};
+use crate::{keys, ring_context::ring_context};
+use rustler::{Binary, Env, NewBinary, NifResult};
 
+/// The commitment as its compressed bytes, see `FixedColumnsCommittedBridge::to_bytes`
 #[rustler::nif]
-pub fn create_commitment(
-    ring: Vec<PublicBridge<S>>,
-) -> NifResult<FixedColumnsCommittedBridge> {
-    let pts: Vec<_> = ring
-        .into_iter()
-        .map(|pk| pk.0)
-        .collect();
+pub fn create_commitment_impl<'a>(
+    env: Env<'a>,
+    ring_size: usize,
+    ring: RingBridge,
+) -> NifResult<Binary<'a>> {
+    let commitment: FixedColumnsCommittedBridge = ring_context(ring_size)?
+        .verifier_key(&ring.0)
+        .commitment()
+        .into();
+    let bytes = commitment.to_bytes()?;
 
-    let commitment = ring_context()?.verifier_key(&pts).commitment();
+    let mut binary = NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok(binary.into())
+}
 
-    Ok(commitment.into())
+#[rustler::nif]
+pub fn valid_public_key_impl(key: Binary) -> bool {
+    keys::valid_public_key(key.as_slice())
 }
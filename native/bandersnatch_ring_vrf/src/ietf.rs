@@ -0,0 +1,60 @@
+//! IETF VRF verification, kept free of NIF types so it can be tested without the BEAM
+
+use crate::types::Bandersnatch as S;
+use ark_vrf::{
+    codec::Codec,
+    ietf::Verifier as _,
+    reexports::ark_serialize::{self, CanonicalDeserialize, CanonicalSerialize},
+    suites::bandersnatch::{IetfProof, Input, Output, Public},
+    Suite,
+};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct IetfVrfSignature {
+    pub output: Output,
+    pub proof: IetfProof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IetfError {
+    InvalidPublicKey,
+    InvalidSignature,
+    InvalidVrfInput,
+    VerificationFailed,
+}
+
+/// Checks `signature` over `vrf_input_data` and `aux_data` against `public`
+pub fn verify(
+    public: &Public,
+    vrf_input_data: &[u8],
+    aux_data: &[u8],
+    signature: &[u8],
+) -> Result<Output, IetfError> {
+    let signature = IetfVrfSignature::deserialize_compressed(signature)
+        .map_err(|_e| IetfError::InvalidSignature)?;
+
+    let input = <S as Suite>::data_to_point(vrf_input_data)
+        .map(Input::from)
+        .ok_or(IetfError::InvalidVrfInput)?;
+    let output = signature.output;
+
+    public
+        .verify(input, output, aux_data, &signature.proof)
+        .map_err(|_| IetfError::VerificationFailed)?;
+
+    Ok(output)
+}
+
+/// Like `verify`, with the public key still encoded
+pub fn verify_encoded(
+    key: &[u8],
+    vrf_input_data: &[u8],
+    aux_data: &[u8],
+    signature: &[u8],
+) -> Result<Output, IetfError> {
+    let public = <<S as Suite>::Codec as Codec<S>>::point_decode(key)
+        .map(Public::from)
+        .map_err(|_| IetfError::InvalidPublicKey)?;
+
+    verify(&public, vrf_input_data, aux_data, signature)
+}
@@ -0,0 +1,58 @@
+use ark_vrf::suites::bandersnatch::{RingProver, RingVerifier};
+use rustler::NifResult;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, LazyLock, Mutex};
+
+// Rings only change once per epoch, so a handful of entries covers the current
+// and next epoch plus the odd one-off ring used by tests or RPC.
+const MAX_CACHED_RINGS: usize = 8;
+
+type Cache<K, V> = LazyLock<Mutex<HashMap<K, Arc<V>>>>;
+
+/// Prepared verifiers keyed by ring size and the serialized ring commitment.
+static VERIFIERS: Cache<(usize, Vec<u8>), RingVerifier> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Prepared provers keyed by ring size, the encoded ring keys and the prover index.
+static PROVERS: Cache<(usize, Vec<u8>, usize), RingProver> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn get_or_try_insert<K, V>(
+    cache: &Mutex<HashMap<K, Arc<V>>>,
+    key: K,
+    init: impl FnOnce() -> NifResult<V>,
+) -> NifResult<Arc<V>>
+where
+    K: Eq + Hash,
+{
+    if let Some(value) = cache.lock().unwrap().get(&key) {
+        return Ok(value.clone());
+    }
+
+    // Build outside the lock, setup is the expensive part we are caching.
+    let value = Arc::new(init()?);
+
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= MAX_CACHED_RINGS {
+        cache.clear();
+    }
+    Ok(cache.entry(key).or_insert(value).clone())
+}
+
+pub fn verifier(
+    ring_size: usize,
+    commitment_key: Vec<u8>,
+    init: impl FnOnce() -> NifResult<RingVerifier>,
+) -> NifResult<Arc<RingVerifier>> {
+    get_or_try_insert(&VERIFIERS, (ring_size, commitment_key), init)
+}
+
+pub fn prover(
+    ring_size: usize,
+    ring_key: Vec<u8>,
+    prover_idx: usize,
+    init: impl FnOnce() -> NifResult<RingProver>,
+) -> NifResult<Arc<RingProver>> {
+    get_or_try_insert(&PROVERS, (ring_size, ring_key, prover_idx), init)
+}
@@ -0,0 +1,7 @@
+use crate::types::Bandersnatch as S;
+use ark_vrf::{codec::Codec, Suite};
+
+/// Whether `key` is the encoding of a curve point
+pub fn valid_public_key(key: &[u8]) -> bool {
+    <<S as Suite>::Codec as Codec<S>>::point_decode(key).is_ok()
+}
@@ -1,8 +1,30 @@
+#[cfg(feature = "nif")]
+mod atoms;
+#[cfg(feature = "nif")]
 mod commitment;
+#[cfg(feature = "nif")]
+mod key_cache;
+pub mod ietf;
+pub mod keys;
+#[cfg(feature = "nif")]
 mod ring_context;
-mod secret_ops;
-mod vrf_operations;
+#[cfg(feature = "nif")]
 mod rustler_bridges;
+#[cfg(feature = "nif")]
+mod secret_ops;
 mod types;
+#[cfg(feature = "nif")]
+mod vrf_operations;
+
+#[cfg(feature = "nif")]
+use crate::rustler_bridges::secret::SecretResource;
+#[cfg(feature = "nif")]
+use rustler::{Env, Term};
+
+#[cfg(feature = "nif")]
+rustler::init!("Elixir.RingVrf", load = load);
 
-rustler::init!("Elixir.RingVrf");
+#[cfg(feature = "nif")]
+fn load(env: Env, _info: Term) -> bool {
+    env.register::<SecretResource>().is_ok()
+}
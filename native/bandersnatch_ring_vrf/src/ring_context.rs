@@ -1,28 +1,43 @@
+use crate::atoms;
 use ark_serialize::CanonicalDeserialize;
 use ark_vrf::{
     reexports::ark_serialize,
     suites::bandersnatch::{PcsParams, RingProofParams},
 };
 use rustler::{Error, NifResult};
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
 
-static RING_CTX: OnceLock<RingProofParams> = OnceLock::new();
+/// Ring contexts keyed by ring size, so chainspecs with different validator
+/// counts can share one node.
+static RING_CTXS: LazyLock<Mutex<HashMap<usize, Arc<RingProofParams>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 static SRS_FILE: &[u8] = include_bytes!("./zcash-srs-2-11-compressed.bin");
 
 #[rustler::nif]
 pub fn create_ring_context(ring_size: usize) -> NifResult<()> {
-    RING_CTX.get_or_init(|| {
-        let pcs_params = PcsParams::deserialize_compressed(&mut &SRS_FILE[..])
-            .expect("Failed to deserialize PCS parameters");
-        RingProofParams::from_pcs_params(ring_size, pcs_params)
-            .expect("Failed to create RingContext")
-    });
+    if RING_CTXS.lock().unwrap().contains_key(&ring_size) {
+        return Ok(());
+    }
+
+    let pcs_params = PcsParams::deserialize_compressed(&mut &SRS_FILE[..])
+        .map_err(|_| Error::Term(Box::new(atoms::srs_deserialization_failed())))?;
+    let ring_ctx = RingProofParams::from_pcs_params(ring_size, pcs_params)
+        .map_err(|_| Error::Term(Box::new(atoms::ring_size_too_large())))?;
+
+    RING_CTXS
+        .lock()
+        .unwrap()
+        .entry(ring_size)
+        .or_insert_with(|| Arc::new(ring_ctx));
     Ok(())
 }
 
-pub fn ring_context() -> Result<RingProofParams, rustler::Error> {
-    RING_CTX
-        .get()
-        .ok_or(Error::Atom("ring_context_not_initialized"))
+pub fn ring_context(ring_size: usize) -> Result<Arc<RingProofParams>, rustler::Error> {
+    RING_CTXS
+        .lock()
+        .unwrap()
+        .get(&ring_size)
         .cloned()
+        .ok_or_else(|| Error::Term(Box::new(atoms::ring_context_not_initialized())))
 }
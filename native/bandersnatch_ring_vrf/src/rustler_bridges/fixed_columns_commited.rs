@@ -6,64 +6,52 @@ use ark_vrf::{
     ring::{RingCommitment, RingSuite},
 };
 
-use rustler::{Decoder, Encoder, Env, NifResult, Term};
+use rustler::{Decoder, Error, NifResult, Term};
 
-use crate::{rustler_bridges::KzgCommitmentBridge, types::Bandersnatch as S};
+use crate::{atoms, rustler_bridges::KzgCommitmentBridge, types::Bandersnatch as S};
 
 type BandersnatchPairing = <S as RingSuite>::Pairing;
+type G1Affine = <BandersnatchPairing as Pairing>::G1Affine;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FixedColumnsCommittedBridge {
-    pub points: Vec<KzgCommitmentBridge<BandersnatchPairing>>,
+    pub points: [KzgCommitmentBridge<BandersnatchPairing>; 2],
     pub ring_selector: KzgCommitmentBridge<BandersnatchPairing>,
 }
 
-impl Encoder for FixedColumnsCommittedBridge {
-    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        let mut points_buf = Vec::new();
-        for point in &self.points {
-            point.0.serialize_compressed(&mut points_buf).unwrap();
+impl FixedColumnsCommittedBridge {
+    /// Compressed points followed by the ring selector, as exchanged with Elixir.
+    pub fn to_bytes(&self) -> NifResult<Vec<u8>> {
+        let mut buf = Vec::new();
+        for point in self.points.iter().chain([&self.ring_selector]) {
+            point
+                .0
+                .serialize_compressed(&mut buf)
+                .map_err(|_| Error::Term(Box::new(atoms::serialization_failed())))?;
         }
-
-        let mut ring_selector_buf = Vec::new();
-        self.ring_selector
-            .0
-            .serialize_compressed(&mut ring_selector_buf)
-            .unwrap();
-
-        // Combine all buffers into a single binary
-        let mut combined_buf = points_buf;
-        combined_buf.extend_from_slice(&ring_selector_buf);
-
-        let mut binary = rustler::OwnedBinary::new(combined_buf.len()).unwrap();
-        binary.as_mut_slice().copy_from_slice(&combined_buf);
-
-        binary.release(env).encode(env)
+        Ok(buf)
     }
 }
 
+fn read_point(
+    reader: &mut std::io::Cursor<&[u8]>,
+) -> NifResult<KzgCommitmentBridge<BandersnatchPairing>> {
+    G1Affine::deserialize_compressed(reader)
+        .map(KzgCommitmentBridge)
+        .map_err(|_| Error::Term(Box::new(atoms::invalid_commitment())))
+}
+
 impl<'a> Decoder<'a> for FixedColumnsCommittedBridge {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         let binary: rustler::Binary = term.decode()?;
         let mut reader = std::io::Cursor::new(binary.as_slice());
 
-        // Deserialize points
-        let mut points = Vec::new();
-        for _ in 0..2 {
-            let point =
-                <BandersnatchPairing as Pairing>::G1Affine::deserialize_compressed(&mut reader)
-                    .map_err(|_| rustler::Error::Atom("deserialization_failed"))?;
-            points.push(KzgCommitmentBridge(point));
-        }
-
-        // Deserialize ring_selector
-        let ring_selector =
-            <<BandersnatchPairing as Pairing>::G1Affine>::deserialize_compressed(&mut reader)
-                .map_err(|_| rustler::Error::Atom("deserialization_failed"))?;
+        let points = [read_point(&mut reader)?, read_point(&mut reader)?];
+        let ring_selector = read_point(&mut reader)?;
 
         Ok(FixedColumnsCommittedBridge {
             points,
-            ring_selector: KzgCommitmentBridge(ring_selector),
+            ring_selector,
         })
     }
 }
@@ -71,13 +59,7 @@ impl<'a> Decoder<'a> for FixedColumnsCommittedBridge {
 impl From<FixedColumnsCommittedBridge> for RingCommitment<S> {
     fn from(bridge: FixedColumnsCommittedBridge) -> Self {
         Self {
-            points: bridge
-                .points
-                .into_iter()
-                .map(|point| point.into())
-                .collect::<Vec<_>>()
-                .try_into()
-                .expect("Conversion failed"),
+            points: bridge.points.map(|point| point.into()),
             ring_selector: bridge.ring_selector.into(),
             phantom: Default::default(),
         }
@@ -87,11 +69,7 @@ impl From<FixedColumnsCommittedBridge> for RingCommitment<S> {
 impl From<RingCommitment<S>> for FixedColumnsCommittedBridge {
     fn from(commitment: RingCommitment<S>) -> Self {
         Self {
-            points: commitment
-                .points
-                .into_iter()
-                .map(|point| point.into())
-                .collect(),
+            points: commitment.points.map(|point| point.into()),
             ring_selector: commitment.ring_selector.into(),
         }
     }
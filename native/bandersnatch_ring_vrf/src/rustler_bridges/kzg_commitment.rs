@@ -1,26 +1,17 @@
-use ark_vrf::reexports::{
-    ark_ec::pairing::Pairing,
-    ark_serialize::{CanonicalDeserialize, CanonicalSerialize},
-};
+use ark_vrf::reexports::{ark_ec::pairing::Pairing, ark_serialize::CanonicalDeserialize};
 use ring_proof::pcs::kzg::commitment::KzgCommitment;
-use rustler::{Decoder, Encoder, Env, NifResult, Term};
+use rustler::{Decoder, NifResult, Term};
+
+use crate::atoms;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KzgCommitmentBridge<E: Pairing>(pub E::G1Affine);
 
-impl<E: Pairing> Encoder for KzgCommitmentBridge<E> {
-    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
-        let mut bytes = Vec::new();
-        self.0.serialize_compressed(&mut bytes).unwrap();
-        bytes.encode(env)
-    }
-}
-
 impl<'a, E: Pairing> Decoder<'a> for KzgCommitmentBridge<E> {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         let bytes: Vec<u8> = term.decode()?;
         let affine = E::G1Affine::deserialize_compressed(&*bytes)
-            .map_err(|_| rustler::Error::Atom("deserialization_failed"))?;
+            .map_err(|_| rustler::Error::Term(Box::new(atoms::invalid_commitment())))?;
         Ok(KzgCommitmentBridge(affine))
     }
 }
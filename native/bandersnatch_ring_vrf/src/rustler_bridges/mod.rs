@@ -2,8 +2,10 @@ pub mod public;
 pub mod secret;
 pub mod kzg_commitment;
 pub mod fixed_columns_commited;
+pub mod ring;
 
 pub use public::PublicBridge;
 pub use secret::SecretBridge;
 pub use kzg_commitment::KzgCommitmentBridge;
-pub use fixed_columns_commited::FixedColumnsCommittedBridge;
\ No newline at end of file
+pub use fixed_columns_commited::FixedColumnsCommittedBridge;
+pub use ring::RingBridge;
\ No newline at end of file
@@ -1,30 +1,38 @@
-use crate::types::Bandersnatch;
-use ark_vrf::{
-    codec::Codec, suites::bandersnatch::RingProofParams, AffinePoint, Public, Suite,
-};
-use rustler::{Decoder, Encoder, Env, NifResult, Term};
+use crate::atoms;
+use ark_vrf::{codec::Codec, AffinePoint, Public, Suite};
+use rustler::{Binary, Decoder, Encoder, Env, Error, NewBinary, NifResult, Term};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct PublicBridge<S: Suite>(pub AffinePoint<S>);
 
+/// Encodes as `{:invalid_public_key, key}` so callers can tell which key was rejected.
+pub struct InvalidPublicKey(pub Vec<u8>);
+
+impl Encoder for InvalidPublicKey {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let mut key_bin = NewBinary::new(env, self.0.len());
+        key_bin.as_mut_slice().copy_from_slice(&self.0);
+
+        (atoms::invalid_public_key(), Binary::from(key_bin)).encode(env)
+    }
+}
+
 impl<S: Suite> Encoder for PublicBridge<S> {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
         let buf = S::Codec::point_encode(&self.0);
 
-        let mut point_bin = rustler::OwnedBinary::new(buf.len()).unwrap();
+        let mut point_bin = NewBinary::new(env, buf.len());
         point_bin.as_mut_slice().copy_from_slice(&buf);
 
-        point_bin.release(env).encode(env)
+        Binary::from(point_bin).encode(env)
     }
 }
-impl<'a, S: Suite + 'a> Decoder<'a> for PublicBridge<S>
-where
-    S: Suite<Affine = AffinePoint<Bandersnatch>>,
-{
+
+impl<'a, S: Suite + 'a> Decoder<'a> for PublicBridge<S> {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         let binary: rustler::Binary = term.decode()?;
-        let point =
-            S::Codec::point_decode(binary.as_slice()).or(Ok(RingProofParams::padding_point()))?;
+        let point = S::Codec::point_decode(binary.as_slice())
+            .map_err(|_| Error::Term(Box::new(InvalidPublicKey(binary.as_slice().to_vec()))))?;
 
         Ok(PublicBridge(point))
     }
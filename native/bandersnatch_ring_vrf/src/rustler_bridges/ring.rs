@@ -0,0 +1,26 @@
+use ark_vrf::suites::bandersnatch::{AffinePoint, RingProofParams};
+use rustler::{types::atom, Atom, Decoder, NifResult, Term};
+
+use crate::{rustler_bridges::PublicBridge, types::Bandersnatch as S};
+
+/// Ring member keys as passed from Elixir.
+///
+/// `nil` marks a nullified (offender) key and is the only way to get the padding
+/// point into a ring; every other member must be a valid Bandersnatch key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingBridge(pub Vec<AffinePoint>);
+
+impl<'a> Decoder<'a> for RingBridge {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let members: Vec<Term<'a>> = term.decode()?;
+
+        members
+            .into_iter()
+            .map(|member| match member.decode::<Atom>() {
+                Ok(a) if a == atom::nil() => Ok(RingProofParams::padding_point()),
+                _ => member.decode::<PublicBridge<S>>().map(|pk| pk.0),
+            })
+            .collect::<NifResult<Vec<_>>>()
+            .map(RingBridge)
+    }
+}
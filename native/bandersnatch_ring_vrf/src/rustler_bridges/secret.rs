@@ -1,58 +1,45 @@
-use ark_vrf::{codec::Codec, AffinePoint, ScalarField, Secret, Suite};
-use rustler::{Decoder, Encoder, NifResult, Term};
+use ark_vrf::Secret;
+use rustler::{Decoder, Encoder, Env, NifResult, Resource, ResourceArc, Term};
 
-use crate::types::Bandersnatch;
+use crate::types::Bandersnatch as S;
 
 use super::PublicBridge;
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct SecretBridge<S: Suite> {
-    pub scalar: ScalarField<S>,
-    pub public: PublicBridge<S>,
-}
-
-impl<S: Suite> Encoder for SecretBridge<S> {
-    fn encode<'b>(&self, env: rustler::Env<'b>) -> Term<'b> {
-        let buf = S::Codec::scalar_encode(&self.scalar);
+/// Secret key kept on the native heap. `Secret` zeroizes its scalar on drop, so
+/// the key is wiped once the last Elixir reference to the handle is collected.
+pub struct SecretResource(pub Secret<S>);
 
-        let mut scalar_bin = rustler::OwnedBinary::new(buf.len()).unwrap();
-        scalar_bin.as_mut_slice().copy_from_slice(&buf);
+impl Resource for SecretResource {}
 
-        let public_term = self.public.encode(env);
+/// Elixir keypair `{secret_handle, public_key}`. Only the opaque handle crosses
+/// into Elixir, the scalar never leaves this crate.
+#[derive(Clone)]
+pub struct SecretBridge(pub ResourceArc<SecretResource>);
 
-        (scalar_bin.release(env), public_term).encode(env)
+impl SecretBridge {
+    pub fn secret(&self) -> &Secret<S> {
+        &self.0 .0
     }
 }
 
-impl<'a, S: Suite + 'a> Decoder<'a> for SecretBridge<S>
-where
-    S: Suite<Affine = AffinePoint<Bandersnatch>>,
-{
-    fn decode(term: Term<'a>) -> NifResult<Self> {
-        let (scalar_bin, public_term): (rustler::Binary, Term<'a>) = term.decode()?;
-
-        let scalar = S::Codec::scalar_decode(scalar_bin.as_slice());
-
-        let public = public_term.decode()?;
+impl Encoder for SecretBridge {
+    fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
+        let public: PublicBridge<S> = self.secret().public().into();
 
-        Ok(SecretBridge { scalar, public })
+        (self.0.encode(env), public.encode(env)).encode(env)
     }
 }
 
-impl<S: Suite> From<Secret<S>> for SecretBridge<S> {
-    fn from(secret: Secret<S>) -> Self {
-        SecretBridge {
-            scalar: secret.scalar,
-            public: secret.public.into(),
-        }
+impl<'a> Decoder<'a> for SecretBridge {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (handle, _public): (ResourceArc<SecretResource>, Term<'a>) = term.decode()?;
+
+        Ok(SecretBridge(handle))
     }
 }
 
-impl<S: Suite> From<SecretBridge<S>> for Secret<S> {
-    fn from(bridge: SecretBridge<S>) -> Self {
-        Secret {
-            scalar: bridge.scalar,
-            public: bridge.public.into(),
-        }
+impl From<Secret<S>> for SecretBridge {
+    fn from(secret: Secret<S>) -> Self {
+        SecretBridge(ResourceArc::new(SecretResource(secret)))
     }
 }
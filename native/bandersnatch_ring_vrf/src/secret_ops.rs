@@ -1,19 +1,22 @@
 use ark_vrf::{reexports::ark_ff::PrimeField, ScalarField, Secret};
 use rand_chacha::rand_core::SeedableRng;
 use rustler::NifResult;
+use zeroize::Zeroize;
 
 use crate::rustler_bridges::{PublicBridge, SecretBridge};
 use crate::types::Bandersnatch as S;
 
 #[rustler::nif]
-fn generate_secret_from_seed(seed: Vec<u8>) -> NifResult<(SecretBridge<S>, PublicBridge<S>)> {
+fn generate_secret_from_seed(seed: Vec<u8>) -> NifResult<(SecretBridge, PublicBridge<S>)> {
+    let mut seed = seed;
     let secret = Secret::from_seed(&seed);
+    seed.zeroize();
     let public = secret.public();
     Ok((secret.into(), public.into()))
 }
 
 #[rustler::nif]
-fn generate_secret_from_rand() -> NifResult<(SecretBridge<S>, PublicBridge<S>)> {
+fn generate_secret_from_rand() -> NifResult<(SecretBridge, PublicBridge<S>)> {
     let mut rng = rand_chacha::ChaCha20Rng::from_entropy();
     let secret = Secret::from_rand(&mut rng);
     let public = secret.public();
@@ -23,8 +26,10 @@ fn generate_secret_from_rand() -> NifResult<(SecretBridge<S>, PublicBridge<S>)>
 #[rustler::nif]
 fn generate_secret_from_scalar(
     scalar_bytes: Vec<u8>,
-) -> NifResult<(SecretBridge<S>, PublicBridge<S>)> {
+) -> NifResult<(SecretBridge, PublicBridge<S>)> {
+    let mut scalar_bytes = scalar_bytes;
     let scalar = ScalarField::<S>::from_le_bytes_mod_order(&scalar_bytes[..]);
+    scalar_bytes.zeroize();
     let secret = Secret::from_scalar(scalar);
     let public = secret.public();
     Ok((secret.into(), public.into()))
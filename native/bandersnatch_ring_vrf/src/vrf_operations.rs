@@ -1,29 +1,25 @@
 use ark_vrf::{
+    codec::Codec,
     reexports::ark_serialize::{self, CanonicalDeserialize, CanonicalSerialize},
-    suites::bandersnatch::{IetfProof, Input, Output, Public, RingProof},
-    Secret,
+    suites::bandersnatch::{Input, Output, Public, RingProof, RingVerifier},
 };
 
-use rustler::{Atom, Binary, Env, Error, NifResult, OwnedBinary};
+use rustler::{Atom, Binary, Encoder, Env, Error, NifResult, OwnedBinary, Term};
+use std::sync::Arc;
 
 use crate::{
+    atoms,
+    ietf::{self, IetfError, IetfVrfSignature},
+    key_cache,
     ring_context::ring_context,
-    rustler_bridges::{FixedColumnsCommittedBridge, PublicBridge, SecretBridge},
+    rustler_bridges::{
+        public::InvalidPublicKey, FixedColumnsCommittedBridge, PublicBridge, RingBridge,
+        SecretBridge,
+    },
     types::Bandersnatch as S,
 };
 
 type RingCommitment = ark_vrf::ring::RingCommitment<S>;
-mod atoms {
-    rustler::atoms! {
-        ok,
-        error,
-        // Define specific error atoms
-        invalid_signature,
-        verification_failed,
-        hash_conversion_failed,
-        unknown_error
-    }
-}
 
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
 struct RingVrfSignature {
@@ -31,145 +27,224 @@ struct RingVrfSignature {
     proof: RingProof,
 }
 
-#[derive(CanonicalSerialize, CanonicalDeserialize)]
-struct IetfVrfSignature {
-    output: Output,
-    proof: IetfProof,
+/// Surfaces a failure to Elixir as `{:error, reason}`.
+fn nif_error(reason: Atom) -> Error {
+    Error::Term(Box::new(reason))
+}
+
+fn vrf_input_point(vrf_input_data: &[u8]) -> Result<Input, Atom> {
+    <S as ark_vrf::Suite>::data_to_point(vrf_input_data)
+        .map(Input::from)
+        .ok_or_else(atoms::invalid_vrf_input)
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Result<Binary<'a>, Atom> {
+    let mut binary = OwnedBinary::new(bytes.len()).ok_or_else(atoms::allocation_failed)?;
+    binary.as_mut_slice().copy_from_slice(bytes);
+    Ok(binary.release(env))
+}
+
+fn serialize<T: CanonicalSerialize>(value: &T) -> Result<Vec<u8>, Atom> {
+    let mut buf = Vec::new();
+    value
+        .serialize_compressed(&mut buf)
+        .map_err(|_| atoms::serialization_failed())?;
+    Ok(buf)
 }
 
-fn vrf_input_point(vrf_input_data: &[u8]) -> Input {
-    let point = <S as ark_vrf::Suite>::data_to_point(vrf_input_data).unwrap();
-    Input::from(point)
+fn output_hash_binary<'a>(env: Env<'a>, output: &Output) -> Result<Binary<'a>, Atom> {
+    let hash = output.hash();
+    let vrf_output_hash = hash.get(..32).ok_or_else(atoms::hash_conversion_failed)?;
+    to_binary(env, vrf_output_hash)
+}
+
+fn ring_verifier(
+    ring_size: usize,
+    commitment: FixedColumnsCommittedBridge,
+) -> NifResult<Arc<RingVerifier>> {
+    key_cache::verifier(ring_size, commitment.to_bytes()?, || {
+        let ring_ctx = ring_context(ring_size)?;
+        let commitment: RingCommitment = commitment.into();
+        let verifier_key = ring_ctx.verifier_key_from_commitment(commitment);
+        Ok(ring_ctx.verifier(verifier_key))
+    })
+}
+
+fn verify_ring_signature(
+    verifier: &RingVerifier,
+    vrf_input_data: &[u8],
+    aux_data: &[u8],
+    signature: &[u8],
+) -> Result<Output, Atom> {
+    use ark_vrf::ring::Verifier as _;
+
+    let signature = RingVrfSignature::deserialize_compressed(signature)
+        .map_err(|_e| atoms::invalid_signature())?;
+
+    let input = vrf_input_point(vrf_input_data)?;
+    let output = signature.output;
+
+    Public::verify(input, output, aux_data, &signature.proof, verifier)
+        .map_err(|_| atoms::verification_failed())?;
+
+    Ok(output)
 }
 
 #[rustler::nif]
 pub fn ring_vrf_verify_impl<'a>(
     env: Env<'a>,
+    ring_size: usize,
     commitment: FixedColumnsCommittedBridge,
     vrf_input_data: Binary,
     aux_data: Binary,
     signature: Binary,
 ) -> NifResult<(Atom, Binary<'a>)> {
-    use ark_vrf::ring::Verifier as _;
-    let commitment: RingCommitment = commitment.into();
-
-    let signature = RingVrfSignature::deserialize_compressed(signature.as_slice())
-        .map_err(|_e| Error::Term(Box::new(atoms::invalid_signature())))?;
-
-    let input = vrf_input_point(&vrf_input_data);
-
-    let output = signature.output;
+    let verifier = ring_verifier(ring_size, commitment)?;
 
-    let ring_ctx = ring_context()?;
-
-    let verifier_key = ring_ctx.verifier_key_from_commitment(commitment);
-    let verifier = ring_ctx.verifier(verifier_key);
-
-    Public::verify(
-        input,
-        output,
-        aux_data.as_slice(),
-        &signature.proof,
+    let output = verify_ring_signature(
         &verifier,
+        vrf_input_data.as_slice(),
+        aux_data.as_slice(),
+        signature.as_slice(),
     )
-    .map_err(|_| Error::Term(Box::new(atoms::verification_failed())))?;
-
-    let vrf_output_hash_vec: Vec<u8> = output.hash()[..32]
-        .try_into()
-        .map_err(|_| Error::Term(Box::new(atoms::hash_conversion_failed())))?;
+    .map_err(nif_error)?;
 
-    let mut vrf_output_hash_bin = OwnedBinary::new(vrf_output_hash_vec.len()).unwrap();
-    vrf_output_hash_bin
-        .as_mut_slice()
-        .copy_from_slice(&vrf_output_hash_vec);
+    Ok((
+        atoms::ok(),
+        output_hash_binary(env, &output).map_err(nif_error)?,
+    ))
+}
 
-    Ok((atoms::ok(), vrf_output_hash_bin.release(env)))
+/// Verifies many signatures against one ring commitment, sharing the verifier setup.
+/// Each item yields `{:ok, output_hash}` or `{:error, reason}`, in input order.
+///
+/// Proofs are still checked one at a time: ark-vrf 0.1.0 and w3f-ring-proof 0.0.2
+/// offer no batched pairing check, so this only saves the per-call verifier
+/// lookup and NIF round trips, not the pairings themselves.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn ring_vrf_verify_batch_impl<'a>(
+    env: Env<'a>,
+    ring_size: usize,
+    commitment: FixedColumnsCommittedBridge,
+    items: Vec<(Binary, Binary, Binary)>,
+) -> NifResult<Vec<Term<'a>>> {
+    let verifier = ring_verifier(ring_size, commitment)?;
+
+    Ok(items
+        .iter()
+        .map(|(vrf_input_data, aux_data, signature)| {
+            match verify_ring_signature(
+                &verifier,
+                vrf_input_data.as_slice(),
+                aux_data.as_slice(),
+                signature.as_slice(),
+            )
+            .and_then(|output| output_hash_binary(env, &output))
+            {
+                Ok(vrf_output_hash) => (atoms::ok(), vrf_output_hash).encode(env),
+                Err(reason) => (atoms::error(), reason).encode(env),
+            }
+        })
+        .collect())
 }
 
 #[rustler::nif]
-fn ring_vrf_sign<'a>(
+fn ring_vrf_sign_impl<'a>(
     env: Env<'a>,
-    ring: Vec<PublicBridge<S>>,
-    secret: SecretBridge<S>,
+    ring_size: usize,
+    ring: RingBridge,
+    secret: SecretBridge,
     prover_idx: usize,
     vrf_input_data: Binary,
     aux_data: Binary,
 ) -> NifResult<(Binary<'a>, Binary<'a>)> {
     use ark_vrf::ring::Prover as _;
 
-    let ring: Vec<Public> = ring.into_iter().map(|pk| pk.into()).collect();
-
-    let input = vrf_input_point(&vrf_input_data);
-    let secret: Secret<S> = secret.into();
+    let input = vrf_input_point(&vrf_input_data).map_err(nif_error)?;
+    let secret = secret.secret();
     let output = secret.output(input);
 
-    let pts: Vec<_> = ring.iter().map(|pk| pk.0).collect();
-
-    let ring_ctx = ring_context()?;
-
-    let prover_key = ring_ctx.prover_key(&pts);
-    let prover = ring_ctx.prover(prover_key, prover_idx);
+    let pts = ring.0;
+    let ring_key: Vec<u8> = pts
+        .iter()
+        .flat_map(<<S as ark_vrf::Suite>::Codec as Codec<S>>::point_encode)
+        .collect();
+
+    let prover = key_cache::prover(ring_size, ring_key, prover_idx, || {
+        let ring_ctx = ring_context(ring_size)?;
+        let prover_key = ring_ctx.prover_key(&pts);
+        Ok(ring_ctx.prover(prover_key, prover_idx))
+    })?;
     let proof = secret.prove(input, output, aux_data.as_slice(), &prover);
 
     let signature = RingVrfSignature { output, proof };
-    let mut buf = Vec::new();
-    signature.serialize_compressed(&mut buf).unwrap();
+    let buf = serialize(&signature).map_err(nif_error)?;
+
+    Ok((
+        to_binary(env, &buf).map_err(nif_error)?,
+        output_hash_binary(env, &output).map_err(nif_error)?,
+    ))
+}
 
-    let mut signature_binary = OwnedBinary::new(buf.len()).unwrap();
-    signature_binary.as_mut_slice().copy_from_slice(&buf);
+/// VRF output hash for `secret` on the given input, without building a proof.
+/// Ring and IETF signatures share this output, so it serves both.
+#[rustler::nif]
+fn vrf_output<'a>(
+    env: Env<'a>,
+    secret: SecretBridge,
+    vrf_input_data: Binary,
+) -> NifResult<Binary<'a>> {
+    let input = vrf_input_point(&vrf_input_data).map_err(nif_error)?;
+    let secret = secret.secret();
+    let output = secret.output(input);
 
-    let vrf_output_hash_vec: Vec<u8> = output.hash()[..32]
-        .try_into()
-        .map_err(|_| Error::Term(Box::new(atoms::hash_conversion_failed())))?;
+    output_hash_binary(env, &output).map_err(nif_error)
+}
 
-    let mut vrf_output_hash_bin = OwnedBinary::new(vrf_output_hash_vec.len()).unwrap();
-    vrf_output_hash_bin
-        .as_mut_slice()
-        .copy_from_slice(&vrf_output_hash_vec);
+/// Output hash carried by a serialized ring or IETF signature. The signature is
+/// NOT verified, use only where the proof is checked elsewhere (e.g. ticket sorting).
+#[rustler::nif]
+fn vrf_signature_output<'a>(env: Env<'a>, signature: Binary) -> NifResult<Binary<'a>> {
+    // Both signature layouts start with the output point.
+    let output = Output::deserialize_compressed(signature.as_slice())
+        .map_err(|_e| nif_error(atoms::invalid_signature()))?;
 
-    Ok((
-        signature_binary.release(env),
-        vrf_output_hash_bin.release(env),
-    ))
+    output_hash_binary(env, &output).map_err(nif_error)
 }
 
 #[rustler::nif]
 fn ietf_vrf_sign<'a>(
     env: Env<'a>,
-    secret_bridge: SecretBridge<S>,
+    secret_bridge: SecretBridge,
     vrf_input_data: Binary,
     aux_data: Binary,
 ) -> NifResult<(Binary<'a>, Binary<'a>)> {
     use ark_vrf::ietf::Prover as _;
 
-    let input = vrf_input_point(&vrf_input_data);
-    let secret: Secret<S> = secret_bridge.into();
+    let input = vrf_input_point(&vrf_input_data).map_err(nif_error)?;
+    let secret = secret_bridge.secret();
     let output = secret.output(input);
 
     let proof = secret.prove(input, output, aux_data.as_slice());
 
     let signature = IetfVrfSignature { output, proof };
-    let mut buf = Vec::new();
-    signature.serialize_compressed(&mut buf).unwrap();
-
-    let mut signature_binary = OwnedBinary::new(buf.len()).unwrap();
-    signature_binary.as_mut_slice().copy_from_slice(&buf);
-
-    let vrf_output_hash_vec: Vec<u8> = output.hash()[..32]
-        .try_into()
-        .map_err(|_| Error::Term(Box::new(atoms::hash_conversion_failed())))?;
-
-    let mut vrf_output_hash_bin = OwnedBinary::new(vrf_output_hash_vec.len()).unwrap();
-    vrf_output_hash_bin
-        .as_mut_slice()
-        .copy_from_slice(&vrf_output_hash_vec);
+    let buf = serialize(&signature).map_err(nif_error)?;
 
     Ok((
-        signature_binary.release(env),
-        vrf_output_hash_bin.release(env),
+        to_binary(env, &buf).map_err(nif_error)?,
+        output_hash_binary(env, &output).map_err(nif_error)?,
     ))
 }
 
+fn ietf_error(error: IetfError) -> Atom {
+    match error {
+        IetfError::InvalidPublicKey => atoms::invalid_public_key(),
+        IetfError::InvalidSignature => atoms::invalid_signature(),
+        IetfError::InvalidVrfInput => atoms::invalid_vrf_input(),
+        IetfError::VerificationFailed => atoms::verification_failed(),
+    }
+}
+
 #[rustler::nif]
 pub fn ietf_vrf_verify<'a>(
     env: Env<'a>,
@@ -178,29 +253,54 @@ pub fn ietf_vrf_verify<'a>(
     aux_data: Binary,
     signature: Binary,
 ) -> NifResult<(Atom, Binary<'a>)> {
-    use ark_vrf::ietf::Verifier as _;
-
-    let signature = IetfVrfSignature::deserialize_compressed(signature.as_slice())
-        .map_err(|_e| Error::Term(Box::new(atoms::invalid_signature())))?;
-
-    let input = vrf_input_point(&vrf_input_data);
-    let output = signature.output;
-
     let public: Public = key.into();
 
-    public
-        .verify(input, output, aux_data.as_slice(), &signature.proof)
-        .map_err(|_| Error::Term(Box::new(atoms::verification_failed())))?;
-
-    let vrf_output_hash_vec: Vec<u8> = match output.hash()[..32].try_into() {
-        Ok(hash) => hash,
-        Err(_) => return Err(Error::Term(Box::new(atoms::hash_conversion_failed()))),
-    };
+    let output = ietf::verify(
+        &public,
+        vrf_input_data.as_slice(),
+        aux_data.as_slice(),
+        signature.as_slice(),
+    )
+    .map_err(|e| nif_error(ietf_error(e)))?;
 
-    let mut vrf_output_hash_bin = OwnedBinary::new(vrf_output_hash_vec.len()).unwrap();
-    vrf_output_hash_bin
-        .as_mut_slice()
-        .copy_from_slice(&vrf_output_hash_vec);
+    Ok((
+        atoms::ok(),
+        output_hash_binary(env, &output).map_err(nif_error)?,
+    ))
+}
 
-    Ok((atoms::ok(), vrf_output_hash_bin.release(env)))
+/// Verifies a list of `{key, input, aux, signature}` tuples, e.g. the seal and
+/// entropy signatures of a run of blocks.
+///
+/// IETF proofs carry only the `(c, s)` scalars, so the commitments needed for a
+/// random linear combination are not available and each item is checked on its own.
+/// Each item yields `{:ok, output_hash}` or `{:error, reason}`, in input order.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn ietf_vrf_verify_batch<'a>(
+    env: Env<'a>,
+    items: Vec<(Binary, Binary, Binary, Binary)>,
+) -> Vec<Term<'a>> {
+    items
+        .iter()
+        .map(|(key, vrf_input_data, aux_data, signature)| {
+            let output = ietf::verify_encoded(
+                key.as_slice(),
+                vrf_input_data.as_slice(),
+                aux_data.as_slice(),
+                signature.as_slice(),
+            );
+
+            if let Err(IetfError::InvalidPublicKey) = output {
+                return (atoms::error(), InvalidPublicKey(key.as_slice().to_vec())).encode(env);
+            }
+
+            match output
+                .map_err(ietf_error)
+                .and_then(|output| output_hash_binary(env, &output))
+            {
+                Ok(vrf_output_hash) => (atoms::ok(), vrf_output_hash).encode(env),
+                Err(reason) => (atoms::error(), reason).encode(env),
+            }
+        })
+        .collect()
 }
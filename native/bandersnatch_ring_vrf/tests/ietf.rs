@@ -0,0 +1,67 @@
+use ark_vrf::{
+    codec::Codec,
+    ietf::Prover,
+    reexports::ark_serialize::CanonicalSerialize,
+    suites::bandersnatch::{BandersnatchSha512Ell2 as S, Input},
+    Secret, Suite,
+};
+use bandersnatch_ring_vrf::ietf::{verify_encoded, IetfError, IetfVrfSignature};
+
+fn sign(secret: &Secret<S>, vrf_input_data: &[u8], aux_data: &[u8]) -> Vec<u8> {
+    let input = Input::from(S::data_to_point(vrf_input_data).unwrap());
+    let output = secret.output(input);
+    let proof = secret.prove(input, output, aux_data);
+
+    let mut signature = Vec::new();
+    IetfVrfSignature { output, proof }
+        .serialize_compressed(&mut signature)
+        .unwrap();
+    signature
+}
+
+fn encoded_key(secret: &Secret<S>) -> Vec<u8> {
+    <<S as Suite>::Codec as Codec<S>>::point_encode(&secret.public().0)
+}
+
+#[test]
+fn test_verify_mixed_entries() {
+    let secret = Secret::<S>::from_seed(&[1; 32]);
+    let other = Secret::<S>::from_seed(&[2; 32]);
+    let (k1, k2) = (encoded_key(&secret), encoded_key(&other));
+    let sig1 = sign(&secret, b"context1", b"message");
+    let sig2 = sign(&secret, b"context2", b"");
+
+    let results: Vec<_> = [
+        (&k1[..], &b"context1"[..], &b"message"[..], &sig1[..]),
+        (&k2, b"context1", b"message", &sig1),
+        (&[0xFF; 32], b"context1", b"message", &sig1),
+        (&k1, b"context2", b"", &[1, 2, 3]),
+        (&k1, b"context1", b"altered", &sig1),
+        (&k1, b"context2", b"", &sig2),
+    ]
+    .iter()
+    .map(|(key, input, aux, signature)| verify_encoded(key, input, aux, signature).err())
+    .collect();
+
+    assert_eq!(
+        results,
+        [
+            None,
+            Some(IetfError::VerificationFailed),
+            Some(IetfError::InvalidPublicKey),
+            Some(IetfError::InvalidSignature),
+            Some(IetfError::VerificationFailed),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn test_verify_returns_the_signed_output() {
+    let secret = Secret::<S>::from_seed(&[3; 32]);
+    let signature = sign(&secret, b"context", b"message");
+    let input = Input::from(S::data_to_point(b"context").unwrap());
+
+    let output = verify_encoded(&encoded_key(&secret), b"context", b"message", &signature);
+    assert_eq!(output.map(|o| o.hash()), Ok(secret.output(input).hash()));
+}
@@ -0,0 +1,17 @@
+use ark_vrf::{codec::Codec, suites::bandersnatch::BandersnatchSha512Ell2 as S, Secret, Suite};
+use bandersnatch_ring_vrf::keys::valid_public_key;
+
+#[test]
+fn test_valid_public_key() {
+    let public = Secret::<S>::from_seed(&[7; 32]).public();
+    let key = <<S as Suite>::Codec as Codec<S>>::point_encode(&public.0);
+
+    assert!(valid_public_key(&key));
+}
+
+#[test]
+fn test_invalid_public_keys() {
+    assert!(!valid_public_key(&[0xFF; 32]));
+    assert!(!valid_public_key(&[1, 2, 3]));
+    assert!(!valid_public_key(&[]));
+}
@@ -1,5 +1,8 @@
-use ed25519_zebra::{Signature, VerificationKey, VerificationKeyBytes};
-use rustler::{Atom, Binary, Error, NifResult};
+use ed25519_zebra::{Signature, SigningKey, VerificationKey, VerificationKeyBytes};
+use rustler::{
+    Atom, Binary, Encoder, Env, Error, NifResult, OwnedBinary, Resource, ResourceArc, Term,
+};
+use zeroize::Zeroize;
 
 mod atoms {
     rustler::atoms! {
@@ -9,51 +12,143 @@ mod atoms {
         invalid_public_key,
         invalid_signature_length,
         invalid_public_key_length,
+        invalid_seed_length,
     }
 }
 
-rustler::init!("Elixir.Util.Crypto.Ed25519Zip215");
+rustler::init!("Elixir.Util.Crypto.Ed25519Zip215", load = load);
 
-/// Verify an Ed25519 signature using ZIP215 rules
+fn load(env: Env, _info: Term) -> bool {
+    env.register::<SigningKeyResource>().is_ok()
+}
+
+/// Signing key held on the native heap and wiped when the handle is collected.
+struct SigningKeyResource(SigningKey);
+
+impl Resource for SigningKeyResource {}
+
+impl Drop for SigningKeyResource {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+fn signing_key_from_seed(seed: &[u8]) -> NifResult<SigningKey> {
+    let mut seed_bytes: [u8; 32] = seed
+        .try_into()
+        .map_err(|_| Error::Term(Box::new(atoms::invalid_seed_length())))?;
+    let signing_key = SigningKey::from(seed_bytes);
+    seed_bytes.zeroize();
+    Ok(signing_key)
+}
+
+/// Derive a signing key from a 32-byte seed (the RFC 8032 private key)
 ///
-/// This implementation:
-/// - Uses the cofactor-8 verification equation
-/// - Accepts non-canonical point encodings
-/// - Requires canonical scalar encoding (s < q)
-/// - Is batch-verification compatible
+/// Returns `{handle, public_key}`; the handle can be passed to `sign/2`
+/// in place of the seed so the secret stays out of the Erlang heap.
 #[rustler::nif]
-fn verify(signature: Binary, message: Binary, public_key: Binary) -> NifResult<Atom> {
+fn keypair_from_seed<'a>(
+    env: Env<'a>,
+    seed: Binary,
+) -> NifResult<(ResourceArc<SigningKeyResource>, Binary<'a>)> {
+    let signing_key = signing_key_from_seed(seed.as_slice())?;
+    let public_key = VerificationKeyBytes::from(&signing_key);
+
+    Ok((
+        ResourceArc::new(SigningKeyResource(signing_key)),
+        to_binary(env, public_key.as_ref()),
+    ))
+}
+
+/// Sign a message with a key handle or a raw 32-byte seed
+#[rustler::nif]
+fn sign<'a>(env: Env<'a>, key: Term<'a>, message: Binary) -> NifResult<Binary<'a>> {
+    let signature = match key.decode::<ResourceArc<SigningKeyResource>>() {
+        Ok(handle) => handle.0.sign(message.as_slice()),
+        Err(_) => {
+            let seed: Binary = key.decode()?;
+            let mut signing_key = signing_key_from_seed(seed.as_slice())?;
+            let signature = signing_key.sign(message.as_slice());
+            signing_key.zeroize();
+            signature
+        }
+    };
+
+    Ok(to_binary(env, &signature.to_bytes()))
+}
+
+/// Public key of a key handle
+#[rustler::nif]
+fn public_key<'a>(env: Env<'a>, key: ResourceArc<SigningKeyResource>) -> Binary<'a> {
+    let public_key = VerificationKeyBytes::from(&key.0);
+    to_binary(env, public_key.as_ref())
+}
+
+/// Parse a signature and public key, reporting the first malformed field
+fn parse_item(
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<(Signature, VerificationKeyBytes), Atom> {
     // Check lengths
     if signature.len() != 64 {
-        return Ok(atoms::invalid_signature_length());
+        return Err(atoms::invalid_signature_length());
     }
 
     if public_key.len() != 32 {
-        return Ok(atoms::invalid_public_key_length());
+        return Err(atoms::invalid_public_key_length());
     }
 
     // Parse signature (64 bytes: R || s)
-    let sig = match Signature::try_from(signature.as_slice()) {
-        Ok(s) => s,
-        Err(_) => return Ok(atoms::invalid_signature()),
-    };
+    let sig = Signature::try_from(signature).map_err(|_| atoms::invalid_signature())?;
 
-    // Parse public key (32 bytes)
-    let pk_bytes = VerificationKeyBytes::try_from(public_key.as_slice())
-        .map_err(|_| Error::Term(Box::new(atoms::invalid_public_key())))?;
+    // Parse public key bytes (32 bytes)
+    let pk_bytes =
+        VerificationKeyBytes::try_from(public_key).map_err(|_| atoms::invalid_public_key())?;
+
+    Ok((sig, pk_bytes))
+}
+
+/// Verify a single item, returning `ok` or the reason it failed
+fn verify_item(signature: &[u8], message: &[u8], public_key: &[u8]) -> Atom {
+    let (sig, pk_bytes) = match parse_item(signature, public_key) {
+        Ok(parsed) => parsed,
+        Err(reason) => return reason,
+    };
 
     let pk = match VerificationKey::try_from(pk_bytes) {
         Ok(k) => k,
-        Err(_) => return Ok(atoms::invalid_public_key()),
+        Err(_) => return atoms::invalid_public_key(),
     };
 
     // Verify signature using ZIP215 rules
-    match pk.verify(&sig, message.as_slice()) {
-        Ok(_) => Ok(atoms::ok()),
-        Err(_) => Ok(atoms::error()),
+    match pk.verify(&sig, message) {
+        Ok(_) => atoms::ok(),
+        Err(_) => atoms::error(),
     }
 }
 
+/// Verify an Ed25519 signature using ZIP215 rules
+///
+/// This implementation:
+/// - Uses the cofactor-8 verification equation
+/// - Accepts non-canonical point encodings
+/// - Requires canonical scalar encoding (s < q)
+/// - Is batch-verification compatible
+#[rustler::nif]
+fn verify(signature: Binary, message: Binary, public_key: Binary) -> NifResult<Atom> {
+    Ok(verify_item(
+        signature.as_slice(),
+        message.as_slice(),
+        public_key.as_slice(),
+    ))
+}
+
 /// Batch verify multiple Ed25519 signatures using ZIP215 rules
 ///
 /// Batch verification is 2-3x faster than individual verification
@@ -103,3 +198,47 @@ fn batch_verify(
         Err(_) => Ok(atoms::error()),
     }
 }
+
+/// Batch verify and report which items failed
+///
+/// Runs the batch equation first; only when it fails are the items
+/// re-verified one by one to find the culprits. Returns `:ok` or
+/// `{:error, [{index, reason}]}` with the same reasons as `verify/3`.
+#[rustler::nif]
+fn batch_verify_detailed<'a>(
+    env: Env<'a>,
+    items: Vec<(Binary, Binary, Binary)>, // Vec<(signature, message, public_key)>
+) -> Term<'a> {
+    use ed25519_zebra::batch;
+
+    let mut verifier = batch::Verifier::new();
+    let mut failures: Vec<(usize, Atom)> = Vec::new();
+
+    for (index, (sig_bin, msg_bin, pk_bin)) in items.iter().enumerate() {
+        match parse_item(sig_bin.as_slice(), pk_bin.as_slice()) {
+            Ok((sig, pk_bytes)) => {
+                verifier.queue(batch::Item::from((pk_bytes, sig, msg_bin.as_slice())))
+            }
+            Err(reason) => failures.push((index, reason)),
+        }
+    }
+
+    if verifier.verify(rand::thread_rng()).is_err() {
+        // The batch equation does not say which item broke it
+        failures = items
+            .iter()
+            .enumerate()
+            .map(|(index, (sig_bin, msg_bin, pk_bin))| {
+                let reason = verify_item(sig_bin.as_slice(), msg_bin.as_slice(), pk_bin.as_slice());
+                (index, reason)
+            })
+            .filter(|(_, reason)| *reason != atoms::ok())
+            .collect();
+    }
+
+    if failures.is_empty() {
+        atoms::ok().encode(env)
+    } else {
+        (atoms::error(), failures).encode(env)
+    }
+}
@@ -0,0 +1,91 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use erasure_coding::{do_decode, do_encode, encode_with_threads, ErasureParams};
+use reed_solomon_simd::ReedSolomonEncoder;
+use std::hint::black_box;
+
+const SEGMENT_SIZE: usize = 4104;
+const BUNDLE_SIZE: usize = 1 << 20;
+
+fn test_data(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 256) as u8).collect()
+}
+
+/// The previous row loop, building a new encoder per row, kept as a baseline
+fn encode_with_encoder_per_row(data: &[u8], params: &ErasureParams) -> Vec<Vec<u8>> {
+    let num_cols = params.original_shards();
+    let symbol_size = params.symbol_size();
+    let col_bytes = data.len().div_ceil(num_cols).div_ceil(symbol_size) * symbol_size;
+
+    let mut padded = data.to_vec();
+    padded.resize(col_bytes * num_cols, 0);
+
+    let mut recovery_shards = vec![Vec::with_capacity(col_bytes); params.recovery_shards()];
+    for row in 0..col_bytes / symbol_size {
+        let mut encoder =
+            ReedSolomonEncoder::new(num_cols, params.recovery_shards(), symbol_size).unwrap();
+        for col in 0..num_cols {
+            let offset = col * col_bytes + row * symbol_size;
+            encoder
+                .add_original_shard(&padded[offset..offset + symbol_size])
+                .unwrap();
+        }
+        let encoded = encoder.encode().unwrap();
+        for (col, symbol) in encoded.recovery_iter().enumerate() {
+            recovery_shards[col].extend_from_slice(symbol);
+        }
+    }
+    recovery_shards
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let params = ErasureParams::full();
+    let mut group = c.benchmark_group("encode");
+    group.sample_size(10);
+
+    for size in [SEGMENT_SIZE, BUNDLE_SIZE] {
+        let data = test_data(size);
+
+        group.bench_with_input(
+            BenchmarkId::new("reused_encoder", size),
+            &data,
+            |b, data| b.iter(|| do_encode(black_box(data.clone()), &params).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parallel_4_threads", size),
+            &data,
+            |b, data| b.iter(|| encode_with_threads(black_box(data), &params, 4).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("encoder_per_row", size),
+            &data,
+            |b, data| b.iter(|| encode_with_encoder_per_row(black_box(data), &params)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let params = ErasureParams::full();
+    let mut group = c.benchmark_group("decode");
+    group.sample_size(10);
+
+    for size in [SEGMENT_SIZE, BUNDLE_SIZE] {
+        let data = test_data(size);
+        let shards = do_encode(data, &params).unwrap();
+
+        // Recovery shards only, the worst case for the decoder
+        let first = params.total_shards() - params.original_shards();
+        let indices: Vec<usize> = (first..params.total_shards()).collect();
+        let selected = shards[first..].to_vec();
+
+        group.bench_function(BenchmarkId::new("recovery_only", size), |b| {
+            b.iter(|| {
+                do_decode(black_box(selected.clone()), indices.clone(), size, &params).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
@@ -16,47 +16,83 @@
 //!   ...
 //! Row N-1:         [Symbol]  [Symbol]  ...  [Symbol]
 //! ```
+//!
+//! Rows are independent, so large inputs are split into contiguous row ranges,
+//! each coded on a shared worker pool and writing straight into its slice of
+//! every shard.
+//!
+//! `encode_into`/`decode_into` write into a caller-provided buffer holding all
+//! shards (or all columns) back to back, so the NIFs can fill a single binary.
 
+use crate::error::ErasureError;
+use crate::params::ErasureParams;
 use reed_solomon_simd::{ReedSolomonDecoder, ReedSolomonEncoder};
-use std::error::Error;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+pub(crate) type Shard = Vec<u8>;
 
-const SYMBOL_SIZE: usize = 2;
+// Below this many rows per thread, handing rows to the pool costs more than it
+// saves (an exported segment is only 6 rows in the full configuration).
+const MIN_ROWS_PER_THREAD: usize = 64;
 
-type Symbol = [u8; 2];
-type Shard = Vec<u8>;
+// Upper bound on the workers of the shared pool, whatever the core count, so
+// concurrent calls cannot take over the machine
+const MAX_POOL_THREADS: usize = 4;
 
-fn to_symbol(slice: &[u8]) -> Symbol {
-    [slice[0], slice[1]]
+/// Workers shared by every call, built on first use
+static POOL: LazyLock<ThreadPool> = LazyLock::new(|| {
+    ThreadPoolBuilder::new()
+        .num_threads(pool_threads())
+        .thread_name(|i| format!("erasure-coding-{i}"))
+        .build()
+        .expect("failed to build the erasure coding pool")
+});
+
+/// Workers in the shared pool: one per core, at most `MAX_POOL_THREADS`
+pub fn pool_threads() -> usize {
+    std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_POOL_THREADS)
 }
 
-struct Config {
-    num_cols: usize,       // C = core count
-    num_rows: usize,       // N = symbols per column
-    recovery_count: usize, // V - C
-    col_bytes: usize,      // bytes per column (shard)
+pub(crate) struct Config {
+    pub(crate) num_cols: usize,       // C = original shard count
+    pub(crate) num_rows: usize,       // N = symbols per column
+    pub(crate) recovery_count: usize, // V - C
+    pub(crate) col_bytes: usize,      // bytes per column (shard)
+    pub(crate) symbol_size: usize,    // bytes per symbol
 }
 
 impl Config {
-    fn new(data_len: usize, num_cols: usize) -> Self {
-        let col_bytes = round_up(data_len.div_ceil(num_cols), SYMBOL_SIZE);
-        let num_rows = col_bytes / SYMBOL_SIZE;
-        let total_shards = if num_cols == 2 { 6 } else { 1023 };
+    pub(crate) fn new(data_len: usize, params: &ErasureParams) -> Self {
+        let num_cols = params.original_shards();
+        let symbol_size = params.symbol_size();
+        let col_bytes = round_up(data_len.div_ceil(num_cols), symbol_size);
+        let num_rows = col_bytes / symbol_size;
 
         Self {
             num_cols,
             num_rows,
-            recovery_count: total_shards - num_cols,
+            recovery_count: params.recovery_shards(),
             col_bytes,
+            symbol_size,
         }
     }
 
-    fn padded_len(&self) -> usize {
-        self.num_rows * self.num_cols * SYMBOL_SIZE
-    }
-
     /// Get symbol at (row, col) from column-major data
     fn symbol_offset(&self, row: usize, col: usize) -> usize {
-        col * self.col_bytes + row * SYMBOL_SIZE
+        col * self.col_bytes + row * self.symbol_size
+    }
+
+    /// Bytes of each column handled by one thread
+    fn chunk_bytes(&self, threads: usize) -> usize {
+        let rows = self
+            .num_rows
+            .div_ceil(threads.max(1))
+            .max(MIN_ROWS_PER_THREAD);
+        rows * self.symbol_size
     }
 }
 
@@ -69,120 +105,348 @@ fn round_up(value: usize, multiple: usize) -> usize {
     }
 }
 
-pub fn do_encode(data: Vec<u8>, core_count: usize) -> Result<Vec<Shard>, Box<dyn Error>> {
-    let config = Config::new(data.len(), core_count);
+/// Symbol at `offset`, zero padded past the end of the data
+fn padded_symbol<'a>(data: &'a [u8], offset: usize, buf: &'a mut [u8]) -> &'a [u8] {
+    let end = offset + buf.len();
+    if end <= data.len() {
+        return &data[offset..end];
+    }
+
+    buf.fill(0);
+    if offset < data.len() {
+        buf[..data.len() - offset].copy_from_slice(&data[offset..]);
+    }
+    buf
+}
+
+/// Split every column into per-thread chunks: `result[t][col]` is thread t's
+/// part of column `col`, covering the same rows for every column.
+fn split_columns(columns: Vec<&mut [u8]>, chunk_bytes: usize) -> Vec<Vec<&mut [u8]>> {
+    let mut per_thread: Vec<Vec<&mut [u8]>> = Vec::new();
+    for column in columns {
+        for (t, chunk) in column.chunks_mut(chunk_bytes).enumerate() {
+            if per_thread.len() <= t {
+                per_thread.push(Vec::new());
+            }
+            per_thread[t].push(chunk);
+        }
+    }
+    per_thread
+}
+
+/// Run `work(first_row, chunks)` for every thread's chunks, inline when there is only one
+///
+/// The chunks run on the shared pool, so however many calls run at once they
+/// never use more than its workers between them.
+fn run_chunks<'a, F>(
+    per_thread: Vec<Vec<&'a mut [u8]>>,
+    chunk_bytes: usize,
+    symbol_size: usize,
+    work: F,
+) -> Result<(), ErasureError>
+where
+    F: Fn(usize, Vec<&'a mut [u8]>) -> Result<(), ErasureError> + Sync,
+{
+    let rows_per_chunk = chunk_bytes / symbol_size;
+
+    if per_thread.len() <= 1 {
+        return per_thread
+            .into_iter()
+            .try_for_each(|chunks| work(0, chunks));
+    }
+
+    let mut results: Vec<Result<(), ErasureError>> = per_thread.iter().map(|_| Ok(())).collect();
+    POOL.scope(|scope| {
+        let work = &work;
+        for ((t, chunks), result) in per_thread.into_iter().enumerate().zip(&mut results) {
+            scope.spawn(move |_| *result = work(t * rows_per_chunk, chunks));
+        }
+    });
+    results.into_iter().collect()
+}
+
+/// Bytes per shard when encoding `data_len` bytes
+pub fn shard_len(data_len: usize, params: &ErasureParams) -> usize {
+    Config::new(data_len, params).col_bytes
+}
+
+/// Fail unless `out` holds exactly `count` shards of `col_bytes` each
+fn check_output_len(out: &[u8], count: usize, config: &Config) -> Result<(), ErasureError> {
+    let expected = count * config.col_bytes;
+    if out.len() != expected {
+        return Err(ErasureError::WrongOutputLength {
+            expected,
+            actual: out.len(),
+        });
+    }
+    Ok(())
+}
+
+pub fn do_encode(data: Vec<u8>, params: &ErasureParams) -> Result<Vec<Shard>, ErasureError> {
+    encode_with_threads(&data, params, 1)
+}
+
+/// Encode borrowed data, splitting rows over up to `threads` threads
+pub fn encode_with_threads(
+    data: &[u8],
+    params: &ErasureParams,
+    threads: usize,
+) -> Result<Vec<Shard>, ErasureError> {
+    let config = Config::new(data.len(), params);
+
+    // pre-allocate place for all the columns, originals first then recovery
+    let mut shards: Vec<Shard> =
+        vec![vec![0u8; config.col_bytes]; config.num_cols + config.recovery_count];
+
+    let columns = shards.iter_mut().map(Vec::as_mut_slice).collect();
+    encode_columns(data, &config, threads, columns)?;
+
+    Ok(shards)
+}
+
+/// Encode into `out`, every shard back to back (`shard_len` bytes each, in index order)
+pub fn encode_into(
+    data: &[u8],
+    params: &ErasureParams,
+    threads: usize,
+    out: &mut [u8],
+) -> Result<(), ErasureError> {
+    let config = Config::new(data.len(), params);
+    check_output_len(out, config.num_cols + config.recovery_count, &config)?;
+
+    if config.col_bytes == 0 {
+        return Ok(());
+    }
+    let columns = out.chunks_mut(config.col_bytes).collect();
+    encode_columns(data, &config, threads, columns)
+}
 
-    // Pad to align with symbol boundaries
-    let mut padded = data;
-    padded.resize(config.padded_len(), 0);
+fn encode_columns(
+    data: &[u8],
+    config: &Config,
+    threads: usize,
+    columns: Vec<&mut [u8]>,
+) -> Result<(), ErasureError> {
+    let chunk_bytes = config.chunk_bytes(threads);
+    run_chunks(
+        split_columns(columns, chunk_bytes),
+        chunk_bytes,
+        config.symbol_size,
+        |first_row, chunks| encode_rows(data, config, first_row, chunks),
+    )
+}
 
-    // pre-allocate place for all the columns
-    let mut original_shards: Vec<Shard> =
-        vec![Vec::with_capacity(config.col_bytes); config.num_cols];
-    let mut recovery_shards: Vec<Shard> =
-        vec![Vec::with_capacity(config.col_bytes); config.recovery_count];
+/// Encode the rows covered by `chunks`, starting at `first_row`
+fn encode_rows(
+    data: &[u8],
+    config: &Config,
+    first_row: usize,
+    mut chunks: Vec<&mut [u8]>,
+) -> Result<(), ErasureError> {
+    // one encoder for all rows, it resets itself when the previous result is dropped
+    let mut encoder =
+        ReedSolomonEncoder::new(config.num_cols, config.recovery_count, config.symbol_size)?;
+    let mut buf = vec![0u8; config.symbol_size];
 
     // encode row by row
-    for row in 0..config.num_rows {
-        let mut encoder =
-            ReedSolomonEncoder::new(config.num_cols, config.recovery_count, SYMBOL_SIZE)?;
+    let rows = chunks[0].len() / config.symbol_size;
+    for r in 0..rows {
+        let at = r * config.symbol_size..(r + 1) * config.symbol_size;
 
         // Each symbol in the row goes to its column
-        for col in 0..config.num_cols {
-            let offset = config.symbol_offset(row, col);
-            let symbol = &padded[offset..offset + SYMBOL_SIZE];
+        for (col, chunk) in chunks[..config.num_cols].iter_mut().enumerate() {
+            let offset = config.symbol_offset(first_row + r, col);
+            let symbol = padded_symbol(data, offset, &mut buf);
             encoder.add_original_shard(symbol)?;
-            original_shards[col].extend_from_slice(symbol);
+            chunk[at.clone()].copy_from_slice(symbol);
         }
 
         // Collect recovery symbols
         let encoded = encoder.encode()?;
-        for (col, symbol) in encoded.recovery_iter().enumerate() {
-            recovery_shards[col].extend_from_slice(symbol);
+        let recovery_chunks = chunks[config.num_cols..].iter_mut();
+        for (chunk, symbol) in recovery_chunks.zip(encoded.recovery_iter()) {
+            chunk[at.clone()].copy_from_slice(symbol);
         }
     }
 
-    Ok(original_shards.into_iter().chain(recovery_shards).collect())
+    Ok(())
 }
 
 pub fn do_decode(
     shards: Vec<Shard>,
     shard_indices: Vec<usize>,
     original_len: usize,
-    core_count: usize,
-) -> Result<Vec<u8>, Box<dyn Error>> {
-    let config = Config::new(original_len, core_count);
+    params: &ErasureParams,
+) -> Result<Vec<u8>, ErasureError> {
+    decode_with_threads(&shards, &shard_indices, original_len, params, 1)
+}
+
+/// Decode borrowed shards, splitting rows over up to `threads` threads
+pub fn decode_with_threads<S: AsRef<[u8]>>(
+    shards: &[S],
+    shard_indices: &[usize],
+    original_len: usize,
+    params: &ErasureParams,
+    threads: usize,
+) -> Result<Vec<u8>, ErasureError> {
+    let config = Config::new(original_len, params);
+
+    // column major output [col0 ++ col1 ++ ... ++ colC-1]
+    let mut original_data = vec![0u8; config.num_cols * config.col_bytes];
+    decode_into(
+        shards,
+        shard_indices,
+        original_len,
+        params,
+        threads,
+        &mut original_data,
+    )?;
+
+    original_data.truncate(original_len);
+    Ok(original_data)
+}
+
+/// Decode into `out`, which holds every padded column (`original_shards * shard_len`
+/// bytes); the data is its first `original_len` bytes
+pub fn decode_into<S: AsRef<[u8]>>(
+    shards: &[S],
+    shard_indices: &[usize],
+    original_len: usize,
+    params: &ErasureParams,
+    threads: usize,
+    out: &mut [u8],
+) -> Result<(), ErasureError> {
+    let config = Config::new(original_len, params);
+    check_output_len(out, config.num_cols, &config)?;
+    validate_shards(shards, shard_indices, &config)?;
+    let shards: Vec<&[u8]> = shards.iter().map(|shard| shard.as_ref()).collect();
 
     // Track which original columns we have
-    // this is used in decode_row to in order to tell if a symbol in the Nth column
+    // this is used in decode_rows to in order to tell if a symbol in the Nth column
     // is coming from the input data or from recovered data
-    let mut have_col: Vec<Option<usize>> = vec![None; core_count];
+    let mut have_col: Vec<Option<usize>> = vec![None; config.num_cols];
     for (pos, &idx) in shard_indices.iter().enumerate() {
-        if idx < core_count {
+        if idx < config.num_cols {
             have_col[idx] = Some(pos);
         }
     }
 
-    //  pre-allocate place for all the columns
-    let mut original_shards: Vec<Shard> =
-        vec![Vec::with_capacity(config.col_bytes); config.num_cols];
+    let chunk_bytes = config.chunk_bytes(threads);
 
-    // decode row by row
-    for row in 0..config.num_rows {
-        let row_symbols = decode_row(row, &shards, &shard_indices, &have_col, &config)?;
+    if config.col_bytes > 0 {
+        let columns = out.chunks_mut(config.col_bytes).collect();
+        run_chunks(
+            split_columns(columns, chunk_bytes),
+            chunk_bytes,
+            config.symbol_size,
+            |first_row, chunks| {
+                decode_rows(
+                    &shards,
+                    shard_indices,
+                    &have_col,
+                    &config,
+                    first_row,
+                    chunks,
+                )
+            },
+        )?;
+    }
 
-        for (col, symbol) in row_symbols.into_iter().enumerate() {
-            original_shards[col].extend_from_slice(&symbol);
+    Ok(())
+}
+
+/// Reject anything that would make the row loop index out of bounds or feed
+/// the decoder an inconsistent shard set
+pub(crate) fn validate_shards<S: AsRef<[u8]>>(
+    shards: &[S],
+    shard_indices: &[usize],
+    config: &Config,
+) -> Result<(), ErasureError> {
+    if shards.len() != shard_indices.len() {
+        return Err(ErasureError::MismatchedCounts {
+            shards: shards.len(),
+            indices: shard_indices.len(),
+        });
+    }
+
+    let total = config.num_cols + config.recovery_count;
+    let mut seen = HashSet::with_capacity(shard_indices.len());
+    for &index in shard_indices {
+        if index >= total {
+            return Err(ErasureError::IndexOutOfRange { index, total });
+        }
+        if !seen.insert(index) {
+            return Err(ErasureError::DuplicateIndex(index));
         }
     }
 
-    // unroll matrix into a vector (column major) [col0 ++ col1 ++ ... ++ colC-1]
-    let original_data: Vec<u8> = original_shards
-        .into_iter()
-        .flatten()
-        .take(original_len)
-        .collect();
+    if shards.len() < config.num_cols {
+        return Err(ErasureError::TooFewShards {
+            needed: config.num_cols,
+            got: shards.len(),
+        });
+    }
 
-    Ok(original_data)
+    for (shard, &index) in shards.iter().zip(shard_indices) {
+        if shard.as_ref().len() != config.col_bytes {
+            return Err(ErasureError::WrongShardLength {
+                index,
+                expected: config.col_bytes,
+                actual: shard.as_ref().len(),
+            });
+        }
+    }
+
+    Ok(())
 }
 
-fn decode_row(
-    row: usize,
-    shards: &[Shard],
+/// Decode the rows covered by `chunks`, one chunk per original column
+fn decode_rows(
+    shards: &[&[u8]],
     shard_indices: &[usize],
     have_col: &[Option<usize>],
     config: &Config,
-) -> Result<Vec<Symbol>, Box<dyn Error>> {
-    // create a decoder  per row, matching the  encoder
-    let mut decoder = ReedSolomonDecoder::new(config.num_cols, config.recovery_count, SYMBOL_SIZE)?;
-
-    let offset = row * SYMBOL_SIZE;
-    for (pos, &shard_idx) in shard_indices.iter().enumerate() {
-        let symbol = &shards[pos][offset..offset + SYMBOL_SIZE];
-
-        if shard_idx < config.num_cols {
-            decoder.add_original_shard(shard_idx, symbol)?;
-        } else {
-            decoder.add_recovery_shard(shard_idx - config.num_cols, symbol)?;
+    first_row: usize,
+    mut chunks: Vec<&mut [u8]>,
+) -> Result<(), ErasureError> {
+    // one decoder for all rows, matching the encoder
+    let mut decoder =
+        ReedSolomonDecoder::new(config.num_cols, config.recovery_count, config.symbol_size)?;
+
+    // decode row by row
+    let rows = chunks[0].len() / config.symbol_size;
+    for r in 0..rows {
+        let offset = (first_row + r) * config.symbol_size;
+        let at = r * config.symbol_size..(r + 1) * config.symbol_size;
+
+        for (pos, &shard_idx) in shard_indices.iter().enumerate() {
+            let symbol = &shards[pos][offset..offset + config.symbol_size];
+
+            if shard_idx < config.num_cols {
+                decoder.add_original_shard(shard_idx, symbol)?;
+            } else {
+                decoder.add_recovery_shard(shard_idx - config.num_cols, symbol)?;
+            }
         }
-    }
 
-    let result = decoder.decode()?;
+        // the decoder resets itself once the result is dropped at the end of the row
+        let result = decoder.decode()?;
 
-    let mut symbols = Vec::with_capacity(config.num_cols);
-    for col in 0..config.num_cols {
-        let symbol = match have_col[col] {
-            // if the column is in the input data, use the original shard
-            Some(pos) => to_symbol(&shards[pos][offset..offset + SYMBOL_SIZE]),
-            // if the column is not in the input data, use the recovered shard
-            None => to_symbol(
-                result
+        for (col, chunk) in chunks.iter_mut().enumerate() {
+            let symbol = match have_col[col] {
+                // if the column is in the input data, use the original shard
+                Some(pos) => &shards[pos][offset..offset + config.symbol_size],
+                // if the column is not in the input data, use the recovered shard
+                None => result
                     .restored_original(col)
-                    .ok_or("Reconstruction failed")?,
-            ),
-        };
-        symbols.push(symbol); // full Symbol row of core_count columns
+                    .ok_or(ErasureError::TooFewShards {
+                        needed: config.num_cols,
+                        got: shard_indices.len(),
+                    })?,
+            };
+            chunk[at.clone()].copy_from_slice(symbol);
+        }
     }
 
-    Ok(symbols)
+    Ok(())
 }
@@ -0,0 +1,211 @@
+//! Erasure root and per-validator justifications
+//!
+//! The erasure root of an availability specification (GP 14.17) is the
+//! well-balanced Merkle root (GP E.3) over one 64-byte leaf per validator:
+//!
+//! ```text
+//! leaf[v] = H(bundle shard v) ++ M_B(segment shards of v)
+//! ```
+//!
+//! A justification is the trace (GP E.2) of a leaf: the sibling of every node
+//! on its path, from the root down. A sibling spanning a single leaf is the raw
+//! 64-byte leaf, anything larger is a 32-byte node hash.
+
+use crate::error::ErasureError;
+use crate::params::ErasureParams;
+use crate::segments::segment_shard_len;
+
+pub type Hash = [u8; 32];
+
+/// One Merkle justification per validator, the sibling hashes along its leaf's path
+pub type Justifications = Vec<Vec<Vec<u8>>>;
+
+const NODE_PREFIX: &[u8] = b"node";
+
+pub fn blake2b_256(data: &[u8]) -> Hash {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(
+        blake2b_simd::Params::new()
+            .hash_length(32)
+            .hash(data)
+            .as_bytes(),
+    );
+    hash
+}
+
+fn hash_node(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut state = blake2b_simd::Params::new().hash_length(32).to_state();
+    state.update(NODE_PREFIX).update(left).update(right);
+    state.finalize().as_bytes().to_vec()
+}
+
+/// N (GP E.1): a single leaf stands for itself, larger lists split at ⌈n/2⌉
+fn node<L: AsRef<[u8]>>(leaves: &[L]) -> Vec<u8> {
+    match leaves {
+        [] => vec![0u8; 32],
+        [leaf] => leaf.as_ref().to_vec(),
+        _ => {
+            let (left, right) = leaves.split_at(leaves.len().div_ceil(2));
+            hash_node(&node(left), &node(right))
+        }
+    }
+}
+
+/// M_B (GP E.3), the well-balanced Merkle root
+pub fn well_balanced_merkle_root<L: AsRef<[u8]>>(leaves: &[L]) -> Hash {
+    match leaves {
+        [leaf] => blake2b_256(leaf.as_ref()),
+        _ => {
+            let mut root = [0u8; 32];
+            root.copy_from_slice(&node(leaves));
+            root
+        }
+    }
+}
+
+/// Leaf of validator `v` from its bundle shard and its concatenated segment shards
+pub fn erasure_leaf(
+    bundle_shard: &[u8],
+    segment_bundle: &[u8],
+    params: &ErasureParams,
+) -> [u8; 64] {
+    let segment_shards: Vec<&[u8]> = segment_bundle.chunks(segment_shard_len(params)).collect();
+
+    let mut leaf = [0u8; 64];
+    leaf[..32].copy_from_slice(&blake2b_256(bundle_shard));
+    leaf[32..].copy_from_slice(&well_balanced_merkle_root(&segment_shards));
+    leaf
+}
+
+/// One leaf per validator; both shard lists are indexed by validator
+pub fn erasure_leaves<B: AsRef<[u8]>, S: AsRef<[u8]>>(
+    bundle_shards: &[B],
+    segment_bundles: &[S],
+    params: &ErasureParams,
+) -> Result<Vec<[u8; 64]>, ErasureError> {
+    for len in [bundle_shards.len(), segment_bundles.len()] {
+        if len != params.total_shards() {
+            return Err(ErasureError::WrongShardCount {
+                expected: params.total_shards(),
+                actual: len,
+            });
+        }
+    }
+
+    Ok(bundle_shards
+        .iter()
+        .zip(segment_bundles)
+        .map(|(bundle, segments)| erasure_leaf(bundle.as_ref(), segments.as_ref(), params))
+        .collect())
+}
+
+pub fn erasure_root<B: AsRef<[u8]>, S: AsRef<[u8]>>(
+    bundle_shards: &[B],
+    segment_bundles: &[S],
+    params: &ErasureParams,
+) -> Result<Hash, ErasureError> {
+    Ok(well_balanced_merkle_root(&erasure_leaves(
+        bundle_shards,
+        segment_bundles,
+        params,
+    )?))
+}
+
+/// Computes the node over `leaves`, appending the sibling at this level to the
+/// path of every leaf below it; paths come out bottom-up.
+fn build_paths<L: AsRef<[u8]>>(leaves: &[L], paths: &mut [Vec<Vec<u8>>]) -> Vec<u8> {
+    match leaves {
+        [] => vec![0u8; 32],
+        [leaf] => leaf.as_ref().to_vec(),
+        _ => {
+            let mid = leaves.len().div_ceil(2);
+            let (left_paths, right_paths) = paths.split_at_mut(mid);
+            let left = build_paths(&leaves[..mid], left_paths);
+            let right = build_paths(&leaves[mid..], right_paths);
+
+            left_paths
+                .iter_mut()
+                .for_each(|path| path.push(right.clone()));
+            right_paths
+                .iter_mut()
+                .for_each(|path| path.push(left.clone()));
+            hash_node(&left, &right)
+        }
+    }
+}
+
+/// The erasure root together with the justification of every validator's leaf
+pub fn erasure_root_with_justifications<B: AsRef<[u8]>, S: AsRef<[u8]>>(
+    bundle_shards: &[B],
+    segment_bundles: &[S],
+    params: &ErasureParams,
+) -> Result<(Hash, Justifications), ErasureError> {
+    let leaves = erasure_leaves(bundle_shards, segment_bundles, params)?;
+    let mut paths = vec![Vec::new(); leaves.len()];
+    build_paths(&leaves, &mut paths);
+
+    for path in paths.iter_mut() {
+        path.reverse();
+    }
+    Ok((well_balanced_merkle_root(&leaves), paths))
+}
+
+/// Recomputes the root from a leaf and its trace, walking the same splits as `node`
+pub fn root_from_justification(
+    leaf: &[u8],
+    index: usize,
+    leaf_count: usize,
+    justification: &[Vec<u8>],
+) -> Option<Hash> {
+    if index >= leaf_count {
+        return None;
+    }
+    if leaf_count == 1 {
+        return justification.is_empty().then(|| blake2b_256(leaf));
+    }
+
+    // Walk down to find on which side of each split the leaf sits
+    let (mut start, mut len) = (0, leaf_count);
+    let mut leaf_on_left = Vec::new();
+    while len > 1 {
+        let mid = len.div_ceil(2);
+        let on_left = index < start + mid;
+        if on_left {
+            len = mid;
+        } else {
+            start += mid;
+            len -= mid;
+        }
+        leaf_on_left.push(on_left);
+    }
+    if leaf_on_left.len() != justification.len() {
+        return None;
+    }
+
+    let mut current = leaf.to_vec();
+    for (on_left, sibling) in leaf_on_left.iter().zip(justification).rev() {
+        current = if *on_left {
+            hash_node(&current, sibling)
+        } else {
+            hash_node(sibling, &current)
+        };
+    }
+
+    let mut root = [0u8; 32];
+    root.copy_from_slice(&current);
+    Some(root)
+}
+
+/// Check the shards received by validator `index` against an erasure root
+pub fn verify_shard(
+    erasure_root: &Hash,
+    index: usize,
+    bundle_shard: &[u8],
+    segment_bundle: &[u8],
+    justification: &[Vec<u8>],
+    params: &ErasureParams,
+) -> bool {
+    let leaf = erasure_leaf(bundle_shard, segment_bundle, params);
+    root_from_justification(&leaf, index, params.total_shards(), justification)
+        .is_some_and(|root| &root == erasure_root)
+}
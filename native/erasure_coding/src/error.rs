@@ -0,0 +1,53 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ErasureError {
+    #[error("got {shards} shards but {indices} shard indices")]
+    MismatchedCounts { shards: usize, indices: usize },
+    #[error("need at least {needed} shards, got {got}")]
+    TooFewShards { needed: usize, got: usize },
+    #[error("shard index {index} is out of range for {total} shards")]
+    IndexOutOfRange { index: usize, total: usize },
+    #[error("shard index {0} appears more than once")]
+    DuplicateIndex(usize),
+    #[error("shard {index} is {actual} bytes, expected {expected}")]
+    WrongShardLength {
+        index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("expected one shard per validator ({expected}), got {actual}")]
+    WrongShardCount { expected: usize, actual: usize },
+    #[error("segment is {0} bytes, expected {size}", size = crate::segments::SEGMENT_SIZE)]
+    WrongSegmentSize(usize),
+    #[error("segment {segment} is out of range for {count} segments")]
+    SegmentOutOfRange { segment: usize, count: usize },
+    #[error("stream expects {expected} bytes, got {got}")]
+    TooMuchData { expected: usize, got: usize },
+    #[error("stream expects {expected} bytes, only {got} were pushed")]
+    IncompleteData { expected: usize, got: usize },
+    #[error("output buffer is {actual} bytes, expected {expected}")]
+    WrongOutputLength { expected: usize, actual: usize },
+    #[error("reed-solomon: {0}")]
+    ReedSolomon(#[from] reed_solomon_simd::Error),
+}
+
+impl ErasureError {
+    /// Atom the NIFs return for this error
+    pub fn atom_name(&self) -> &'static str {
+        match self {
+            Self::MismatchedCounts { .. } => "mismatched_counts",
+            Self::TooFewShards { .. } => "too_few_shards",
+            Self::IndexOutOfRange { .. } => "index_out_of_range",
+            Self::DuplicateIndex(_) => "duplicate_index",
+            Self::WrongShardLength { .. } => "wrong_shard_length",
+            Self::WrongShardCount { .. } => "wrong_shard_count",
+            Self::WrongSegmentSize(_) => "wrong_segment_size",
+            Self::SegmentOutOfRange { .. } => "segment_out_of_range",
+            Self::TooMuchData { .. } => "too_much_data",
+            Self::IncompleteData { .. } => "incomplete_data",
+            Self::WrongOutputLength { .. } => "wrong_output_length",
+            Self::ReedSolomon(_) => "error",
+        }
+    }
+}
@@ -1,58 +1,383 @@
 pub mod erasure_codec;
+pub mod erasure_root;
+pub mod error;
+pub mod params;
+pub mod reconstruct;
+pub mod segments;
+pub mod stream;
 
 // Re-export the core functions for use by other Rust code
-pub use erasure_codec::{do_decode, do_encode};
+pub use erasure_codec::{
+    decode_into, decode_with_threads, do_decode, do_encode, encode_into, encode_with_threads,
+    shard_len,
+};
+pub use erasure_root::{
+    erasure_root, erasure_root_with_justifications, verify_shard, well_balanced_merkle_root,
+    Justifications,
+};
+pub use error::ErasureError;
+pub use params::{ErasureParams, ParamsError};
+pub use reconstruct::{reconstruct_shards, verify_shards};
+pub use segments::{decode_segments, encode_segments, segment_shard_len, SEGMENT_SIZE};
+pub use stream::{StreamingDecoder, StreamingEncoder};
 
 #[cfg(feature = "nif")]
-use rustler::{Binary, NifResult};
+use rustler::{Binary, Env, NifResult, Resource, ResourceArc, Term};
+#[cfg(feature = "nif")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "nif")]
+use std::sync::Mutex;
+
+/// Row ranges per encode/decode call, 0 = one per worker of the shared pool
+#[cfg(feature = "nif")]
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "nif")]
+fn nif_threads() -> usize {
+    match THREADS.load(Ordering::Relaxed) {
+        0 => erasure_codec::pool_threads(),
+        threads => threads.min(erasure_codec::pool_threads()),
+    }
+}
 
+/// Set the threads used by `encode`/`decode`, 0 restores one per pool worker
 #[cfg(feature = "nif")]
 #[rustler::nif]
+fn set_threads(threads: usize) -> rustler::Atom {
+    THREADS.store(threads, Ordering::Relaxed);
+    rustler::types::atom::ok()
+}
+
+/// Parameters arrive from Elixir as `{original_shards, total_shards, symbol_size}`
+#[cfg(feature = "nif")]
+fn erasure_params(params: (usize, usize, usize)) -> NifResult<ErasureParams> {
+    let (original_shards, total_shards, symbol_size) = params;
+    ErasureParams::new(original_shards, total_shards, symbol_size)
+        .map_err(|_| rustler::Error::Atom("invalid_params"))
+}
+
+/// Each error variant surfaces as its own atom
+#[cfg(feature = "nif")]
+fn nif_error(err: ErasureError) -> rustler::Error {
+    rustler::Error::Atom(err.atom_name())
+}
+
+/// All shards are encoded into one binary and handed out as sub-binaries of it
+#[cfg(feature = "nif")]
+#[rustler::nif(schedule = "DirtyCpu")]
 fn encode<'a>(
     env: rustler::Env<'a>,
     data_binary: Binary<'a>,
-    c: usize,
+    params: (usize, usize, usize),
 ) -> NifResult<Vec<Binary<'a>>> {
-    let data = data_binary.as_slice().to_vec();
-    let result = do_encode(data, c)
-        .map(|shards| {
-            shards
-                .into_iter()
-                .map(|shard| {
-                    let mut owned_binary = rustler::OwnedBinary::new(shard.len()).unwrap();
-                    owned_binary.as_mut_slice().copy_from_slice(&shard);
-                    Binary::from_owned(owned_binary, env)
-                })
-                .collect()
-        })
-        .map_err(|_| rustler::Error::Atom("error"));
+    let params = erasure_params(params)?;
+    let data = data_binary.as_slice();
+    let shard_len = shard_len(data.len(), &params);
+
+    let mut owned_binary = rustler::OwnedBinary::new(shard_len * params.total_shards()).unwrap();
+    encode_into(data, &params, nif_threads(), owned_binary.as_mut_slice()).map_err(nif_error)?;
 
-    result
+    let shards = Binary::from_owned(owned_binary, env);
+    (0..params.total_shards())
+        .map(|i| shards.make_subbinary(i * shard_len, shard_len))
+        .collect()
 }
 
+/// Decodes straight out of the borrowed shards into the padded columns and
+/// returns the data as a sub-binary of them
 #[cfg(feature = "nif")]
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 fn decode<'a>(
     env: rustler::Env<'a>,
     shards: Vec<Binary<'a>>,
     indexes: Vec<usize>,
     original_size: usize,
-    c: usize,
+    params: (usize, usize, usize),
 ) -> NifResult<Binary<'a>> {
-    let shards_binaries: Vec<Vec<u8>> = shards
+    let params = erasure_params(params)?;
+    let shards: Vec<&[u8]> = shards.iter().map(|shard| shard.as_slice()).collect();
+    let padded_len = shard_len(original_size, &params) * params.original_shards();
+
+    let mut owned_binary = rustler::OwnedBinary::new(padded_len).unwrap();
+    decode_into(
+        &shards,
+        &indexes,
+        original_size,
+        &params,
+        nif_threads(),
+        owned_binary.as_mut_slice(),
+    )
+    .map_err(nif_error)?;
+
+    Binary::from_owned(owned_binary, env).make_subbinary(0, original_size)
+}
+
+#[cfg(feature = "nif")]
+fn to_binaries<'a>(env: rustler::Env<'a>, shards: Vec<Vec<u8>>) -> Vec<Binary<'a>> {
+    shards
         .into_iter()
-        .map(|shard| shard.as_slice().to_vec())
-        .collect();
-    let result = do_decode(shards_binaries, indexes, original_size, c)
-        .map(|decoded| {
-            let mut owned_binary = rustler::OwnedBinary::new(decoded.len()).unwrap();
-            owned_binary.as_mut_slice().copy_from_slice(&decoded);
+        .map(|shard| {
+            let mut owned_binary = rustler::OwnedBinary::new(shard.len()).unwrap();
+            owned_binary.as_mut_slice().copy_from_slice(&shard);
             Binary::from_owned(owned_binary, env)
         })
-        .map_err(|_| rustler::Error::Atom("error"));
+        .collect()
+}
+
+/// Encode exported segments into one shard bundle per validator
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "encode_segments", schedule = "DirtyCpu")]
+fn encode_segments_nif<'a>(
+    env: rustler::Env<'a>,
+    segments: Vec<Binary<'a>>,
+    params: (usize, usize, usize),
+) -> NifResult<Vec<Binary<'a>>> {
+    let params = erasure_params(params)?;
+    let segments: Vec<&[u8]> = segments.iter().map(|segment| segment.as_slice()).collect();
+
+    encode_segments(&segments, &params)
+        .map(|bundles| to_binaries(env, bundles))
+        .map_err(nif_error)
+}
+
+/// Rebuild the requested segments from per-validator shard bundles
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "decode_segments", schedule = "DirtyCpu")]
+fn decode_segments_nif<'a>(
+    env: rustler::Env<'a>,
+    bundles: Vec<Binary<'a>>,
+    validator_indices: Vec<usize>,
+    segment_indices: Vec<usize>,
+    params: (usize, usize, usize),
+) -> NifResult<Vec<Binary<'a>>> {
+    let params = erasure_params(params)?;
+    let bundles: Vec<&[u8]> = bundles.iter().map(|bundle| bundle.as_slice()).collect();
+
+    decode_segments(&bundles, &validator_indices, &segment_indices, &params)
+        .map(|segments| to_binaries(env, segments))
+        .map_err(nif_error)
+}
+
+/// Erasure root over per-validator bundle shards and segment shard bundles
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "erasure_root", schedule = "DirtyCpu")]
+fn erasure_root_nif<'a>(
+    env: rustler::Env<'a>,
+    bundle_shards: Vec<Binary<'a>>,
+    segment_bundles: Vec<Binary<'a>>,
+    params: (usize, usize, usize),
+) -> NifResult<Binary<'a>> {
+    let params = erasure_params(params)?;
+    let bundle_shards: Vec<&[u8]> = bundle_shards.iter().map(|b| b.as_slice()).collect();
+    let segment_bundles: Vec<&[u8]> = segment_bundles.iter().map(|b| b.as_slice()).collect();
+
+    erasure_root(&bundle_shards, &segment_bundles, &params)
+        .map(|root| to_binaries(env, vec![root.to_vec()]).remove(0))
+        .map_err(nif_error)
+}
+
+/// `{erasure_root, justifications}` with one justification per validator
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "erasure_root_with_justifications", schedule = "DirtyCpu")]
+fn erasure_root_with_justifications_nif<'a>(
+    env: rustler::Env<'a>,
+    bundle_shards: Vec<Binary<'a>>,
+    segment_bundles: Vec<Binary<'a>>,
+    params: (usize, usize, usize),
+) -> NifResult<(Binary<'a>, Vec<Vec<Binary<'a>>>)> {
+    let params = erasure_params(params)?;
+    let bundle_shards: Vec<&[u8]> = bundle_shards.iter().map(|b| b.as_slice()).collect();
+    let segment_bundles: Vec<&[u8]> = segment_bundles.iter().map(|b| b.as_slice()).collect();
+
+    let (root, justifications) =
+        erasure_root_with_justifications(&bundle_shards, &segment_bundles, &params)
+            .map_err(nif_error)?;
+
+    Ok((
+        to_binaries(env, vec![root.to_vec()]).remove(0),
+        justifications
+            .into_iter()
+            .map(|justification| to_binaries(env, justification))
+            .collect(),
+    ))
+}
+
+/// Check a validator's shards against an erasure root and its justification
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "verify_shard")]
+fn verify_shard_nif(
+    erasure_root: Binary,
+    index: usize,
+    bundle_shard: Binary,
+    segment_bundle: Binary,
+    justification: Vec<Binary>,
+    params: (usize, usize, usize),
+) -> NifResult<bool> {
+    let params = erasure_params(params)?;
+    let Ok(erasure_root) = erasure_root.as_slice().try_into() else {
+        return Ok(false);
+    };
+    let justification: Vec<Vec<u8>> = justification
+        .iter()
+        .map(|j| j.as_slice().to_vec())
+        .collect();
+
+    Ok(verify_shard(
+        &erasure_root,
+        index,
+        bundle_shard.as_slice(),
+        segment_bundle.as_slice(),
+        &justification,
+        &params,
+    ))
+}
 
-    result
+/// Rebuild the shards at `wanted` (original or recovery) from a sufficient subset
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "reconstruct_shards", schedule = "DirtyCpu")]
+fn reconstruct_shards_nif<'a>(
+    env: rustler::Env<'a>,
+    shards: Vec<Binary<'a>>,
+    indexes: Vec<usize>,
+    wanted: Vec<usize>,
+    original_size: usize,
+    params: (usize, usize, usize),
+) -> NifResult<Vec<Binary<'a>>> {
+    let params = erasure_params(params)?;
+    let shards: Vec<&[u8]> = shards.iter().map(|shard| shard.as_slice()).collect();
+
+    reconstruct_shards(&shards, &indexes, &wanted, original_size, &params)
+        .map(|rebuilt| to_binaries(env, rebuilt))
+        .map_err(nif_error)
+}
+
+/// Whether all shards, in index order, belong to one codeword
+#[cfg(feature = "nif")]
+#[rustler::nif(name = "verify_shards", schedule = "DirtyCpu")]
+fn verify_shards_nif(shards: Vec<Binary>, params: (usize, usize, usize)) -> NifResult<bool> {
+    let params = erasure_params(params)?;
+    let shards: Vec<&[u8]> = shards.iter().map(|shard| shard.as_slice()).collect();
+
+    verify_shards(&shards, &params).map_err(nif_error)
+}
+
+/// Streaming coder handle, emptied once finished
+#[cfg(feature = "nif")]
+struct StreamResource<T>(Mutex<Option<T>>);
+
+#[cfg(feature = "nif")]
+impl Resource for StreamResource<StreamingEncoder> {}
+
+#[cfg(feature = "nif")]
+impl Resource for StreamResource<StreamingDecoder> {}
+
+#[cfg(feature = "nif")]
+impl<T> StreamResource<T> {
+    fn new(inner: T) -> ResourceArc<Self>
+    where
+        Self: Resource,
+    {
+        ResourceArc::new(Self(Mutex::new(Some(inner))))
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> Result<R, ErasureError>) -> NifResult<R> {
+        let mut inner = self.0.lock().unwrap();
+        let inner = inner.as_mut().ok_or(rustler::Error::Atom("finished"))?;
+        f(inner).map_err(nif_error)
+    }
+
+    fn take(&self) -> NifResult<T> {
+        self.0
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(rustler::Error::Atom("finished"))
+    }
+}
+
+/// Start encoding `data_len` bytes that will arrive through `encoder_push`
+#[cfg(feature = "nif")]
+#[rustler::nif]
+fn encoder_new(
+    data_len: usize,
+    params: (usize, usize, usize),
+) -> NifResult<ResourceArc<StreamResource<StreamingEncoder>>> {
+    let params = erasure_params(params)?;
+    let encoder = StreamingEncoder::new(data_len, &params).map_err(nif_error)?;
+    Ok(StreamResource::new(encoder))
+}
+
+#[cfg(feature = "nif")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encoder_push(
+    encoder: ResourceArc<StreamResource<StreamingEncoder>>,
+    chunk: Binary,
+) -> NifResult<rustler::Atom> {
+    encoder.with(|encoder| encoder.push(chunk.as_slice()))?;
+    Ok(rustler::types::atom::ok())
+}
+
+/// The part of every shard that became final since the last drain, in shard order
+#[cfg(feature = "nif")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encoder_drain<'a>(
+    env: rustler::Env<'a>,
+    encoder: ResourceArc<StreamResource<StreamingEncoder>>,
+) -> NifResult<Vec<Binary<'a>>> {
+    let ready = encoder.with(|encoder| Ok(encoder.drain()))?;
+    Ok(to_binaries(env, ready))
+}
+
+#[cfg(feature = "nif")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn encoder_finish<'a>(
+    env: rustler::Env<'a>,
+    encoder: ResourceArc<StreamResource<StreamingEncoder>>,
+) -> NifResult<Vec<Binary<'a>>> {
+    let shards = encoder.take()?.finish().map_err(nif_error)?;
+    Ok(to_binaries(env, shards))
+}
+
+/// Start collecting shards of `original_len` bytes of data
+#[cfg(feature = "nif")]
+#[rustler::nif]
+fn decoder_new(
+    original_len: usize,
+    params: (usize, usize, usize),
+) -> NifResult<ResourceArc<StreamResource<StreamingDecoder>>> {
+    let params = erasure_params(params)?;
+    Ok(StreamResource::new(StreamingDecoder::new(
+        original_len,
+        &params,
+    )))
+}
+
+/// Add one validator's shard, returning whether enough shards have arrived
+#[cfg(feature = "nif")]
+#[rustler::nif]
+fn decoder_add_shard(
+    decoder: ResourceArc<StreamResource<StreamingDecoder>>,
+    index: usize,
+    shard: Binary,
+) -> NifResult<bool> {
+    decoder.with(|decoder| decoder.add_shard(index, shard.as_slice()))
+}
+
+#[cfg(feature = "nif")]
+#[rustler::nif(schedule = "DirtyCpu")]
+fn decoder_decode<'a>(
+    env: rustler::Env<'a>,
+    decoder: ResourceArc<StreamResource<StreamingDecoder>>,
+) -> NifResult<Binary<'a>> {
+    let decoded = decoder.with(|decoder| decoder.decode())?;
+    Ok(to_binaries(env, vec![decoded]).remove(0))
+}
+
+#[cfg(feature = "nif")]
+fn load(env: Env, _info: Term) -> bool {
+    env.register::<StreamResource<StreamingEncoder>>().is_ok()
+        && env.register::<StreamResource<StreamingDecoder>>().is_ok()
 }
 
 #[cfg(feature = "nif")]
-rustler::init!("Elixir.ErasureCoding");
+rustler::init!("Elixir.ErasureCoding", load = load);
@@ -0,0 +1,128 @@
+//! Erasure-coding parameters of a chainspec
+//!
+//! JAM codes every blob into one shard per validator, any `original_shards` of
+//! which are enough to recover the data. The named configurations follow the
+//! JAM testnet chainspecs, where a third of the validators can always recover.
+
+use reed_solomon_simd::ReedSolomonEncoder;
+use thiserror::Error;
+
+/// Symbol size in bytes used by every JAM configuration (GF(2^16) words).
+pub const JAM_SYMBOL_SIZE: usize = 2;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParamsError {
+    #[error("original shard count must be at least 1")]
+    NoOriginalShards,
+    #[error("total shard count {total} must exceed original shard count {original}")]
+    NoRecoveryShards { original: usize, total: usize },
+    #[error("{original} original and {recovery} recovery shards are not supported")]
+    UnsupportedShardCount { original: usize, recovery: usize },
+    #[error("symbol size {0} must be a non-zero multiple of 2")]
+    InvalidSymbolSize(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErasureParams {
+    original_shards: usize, // data shards needed for recovery
+    total_shards: usize,    // V = one shard per validator
+    symbol_size: usize,     // bytes per Reed-Solomon symbol
+}
+
+impl ErasureParams {
+    pub fn new(
+        original_shards: usize,
+        total_shards: usize,
+        symbol_size: usize,
+    ) -> Result<Self, ParamsError> {
+        if original_shards == 0 {
+            return Err(ParamsError::NoOriginalShards);
+        }
+        if total_shards <= original_shards {
+            return Err(ParamsError::NoRecoveryShards {
+                original: original_shards,
+                total: total_shards,
+            });
+        }
+        let recovery_shards = total_shards - original_shards;
+        if !ReedSolomonEncoder::supports(original_shards, recovery_shards) {
+            return Err(ParamsError::UnsupportedShardCount {
+                original: original_shards,
+                recovery: recovery_shards,
+            });
+        }
+        if symbol_size == 0 || !symbol_size.is_multiple_of(2) {
+            return Err(ParamsError::InvalidSymbolSize(symbol_size));
+        }
+
+        Ok(Self {
+            original_shards,
+            total_shards,
+            symbol_size,
+        })
+    }
+
+    const fn jam(original_shards: usize, total_shards: usize) -> Self {
+        Self {
+            original_shards,
+            total_shards,
+            symbol_size: JAM_SYMBOL_SIZE,
+        }
+    }
+
+    /// V = 6
+    pub const fn tiny() -> Self {
+        Self::jam(2, 6)
+    }
+
+    /// V = 12
+    pub const fn small() -> Self {
+        Self::jam(4, 12)
+    }
+
+    /// V = 18
+    pub const fn medium() -> Self {
+        Self::jam(6, 18)
+    }
+
+    /// V = 36
+    pub const fn large() -> Self {
+        Self::jam(12, 36)
+    }
+
+    /// V = 72
+    pub const fn xlarge() -> Self {
+        Self::jam(24, 72)
+    }
+
+    /// V = 144
+    pub const fn xxlarge() -> Self {
+        Self::jam(48, 144)
+    }
+
+    /// V = 288
+    pub const fn xxxlarge() -> Self {
+        Self::jam(96, 288)
+    }
+
+    /// V = 1023, 342:1023 as in the Graypaper
+    pub const fn full() -> Self {
+        Self::jam(342, 1023)
+    }
+
+    pub const fn original_shards(&self) -> usize {
+        self.original_shards
+    }
+
+    pub const fn total_shards(&self) -> usize {
+        self.total_shards
+    }
+
+    pub const fn recovery_shards(&self) -> usize {
+        self.total_shards - self.original_shards
+    }
+
+    pub const fn symbol_size(&self) -> usize {
+        self.symbol_size
+    }
+}
@@ -0,0 +1,158 @@
+//! Shard-level recovery
+//!
+//! An assurer holding enough shards can rebuild any other shard, original or
+//! recovery, row by row: decode the row's original symbols, re-encode them only
+//! when a missing recovery shard is asked for, and keep just the requested
+//! symbols. Neither the data nor the full shard set is ever materialised.
+
+use crate::erasure_codec::{validate_shards, Config, Shard};
+use crate::error::ErasureError;
+use crate::params::ErasureParams;
+use reed_solomon_simd::{ReedSolomonDecoder, ReedSolomonEncoder};
+
+/// Rebuild the shards at `wanted` from any sufficient subset of shards
+pub fn reconstruct_shards<S: AsRef<[u8]>>(
+    shards: &[S],
+    shard_indices: &[usize],
+    wanted: &[usize],
+    original_len: usize,
+    params: &ErasureParams,
+) -> Result<Vec<Shard>, ErasureError> {
+    let config = Config::new(original_len, params);
+    validate_shards(shards, shard_indices, &config)?;
+
+    let total = params.total_shards();
+    if let Some(&index) = wanted.iter().find(|&&index| index >= total) {
+        return Err(ErasureError::IndexOutOfRange { index, total });
+    }
+
+    let shards: Vec<&[u8]> = shards.iter().map(|shard| shard.as_ref()).collect();
+    let position = |index: usize| shard_indices.iter().position(|&i| i == index);
+
+    // shards we already hold are copied as they are
+    let sources: Vec<Option<usize>> = wanted.iter().map(|&index| position(index)).collect();
+    if sources.iter().all(Option::is_some) {
+        return Ok(sources
+            .iter()
+            .flatten()
+            .map(|&pos| shards[pos].to_vec())
+            .collect());
+    }
+
+    let have_col: Vec<Option<usize>> = (0..config.num_cols).map(position).collect();
+    let needs_recovery = wanted
+        .iter()
+        .zip(&sources)
+        .any(|(&index, source)| index >= config.num_cols && source.is_none());
+
+    let ss = config.symbol_size;
+    let mut decoder = ReedSolomonDecoder::new(config.num_cols, config.recovery_count, ss)?;
+    let mut encoder = needs_recovery
+        .then(|| ReedSolomonEncoder::new(config.num_cols, config.recovery_count, ss))
+        .transpose()?;
+
+    let mut row_originals = vec![0u8; config.num_cols * ss];
+    let mut rebuilt: Vec<Shard> = vec![Vec::with_capacity(config.col_bytes); wanted.len()];
+
+    for row in 0..config.num_rows {
+        let symbols = row * ss..(row + 1) * ss;
+
+        // decode the original symbols of this row
+        for (pos, &shard_idx) in shard_indices.iter().enumerate() {
+            let symbol = &shards[pos][symbols.clone()];
+            if shard_idx < config.num_cols {
+                decoder.add_original_shard(shard_idx, symbol)?;
+            } else {
+                decoder.add_recovery_shard(shard_idx - config.num_cols, symbol)?;
+            }
+        }
+
+        let result = decoder.decode()?;
+        for (col, original) in row_originals.chunks_mut(ss).enumerate() {
+            let symbol = match have_col[col] {
+                Some(pos) => &shards[pos][symbols.clone()],
+                None => result
+                    .restored_original(col)
+                    .ok_or(ErasureError::TooFewShards {
+                        needed: config.num_cols,
+                        got: shard_indices.len(),
+                    })?,
+            };
+            original.copy_from_slice(symbol);
+        }
+
+        // re-encode the row only if a missing recovery shard was asked for
+        let encoded = match encoder.as_mut() {
+            Some(encoder) => {
+                for original in row_originals.chunks(ss) {
+                    encoder.add_original_shard(original)?;
+                }
+                Some(encoder.encode()?)
+            }
+            None => None,
+        };
+
+        for ((shard, &index), source) in rebuilt.iter_mut().zip(wanted).zip(&sources) {
+            let symbol = match (source, &encoded) {
+                (Some(pos), _) => &shards[*pos][symbols.clone()],
+                (None, _) if index < config.num_cols => {
+                    &row_originals[index * ss..(index + 1) * ss]
+                }
+                (None, Some(encoded)) => encoded
+                    .recovery(index - config.num_cols)
+                    .expect("recovery index checked against total"),
+                (None, None) => unreachable!("encoder exists when recovery shards are missing"),
+            };
+            shard.extend_from_slice(symbol);
+        }
+    }
+
+    Ok(rebuilt)
+}
+
+/// Check that a full set of shards, in index order, forms a single codeword
+pub fn verify_shards<S: AsRef<[u8]>>(
+    shards: &[S],
+    params: &ErasureParams,
+) -> Result<bool, ErasureError> {
+    if shards.len() != params.total_shards() {
+        return Err(ErasureError::WrongShardCount {
+            expected: params.total_shards(),
+            actual: shards.len(),
+        });
+    }
+
+    let ss = params.symbol_size();
+    let shard_len = shards[0].as_ref().len();
+    for (index, shard) in shards.iter().enumerate() {
+        let actual = shard.as_ref().len();
+        if actual != shard_len || actual % ss != 0 {
+            return Err(ErasureError::WrongShardLength {
+                index,
+                expected: shard_len - shard_len % ss,
+                actual,
+            });
+        }
+    }
+
+    let (originals, recovery) = shards.split_at(params.original_shards());
+    let mut encoder = ReedSolomonEncoder::new(originals.len(), recovery.len(), ss)?;
+
+    for row in 0..shard_len / ss {
+        let symbols = row * ss..(row + 1) * ss;
+        for original in originals {
+            encoder.add_original_shard(&original.as_ref()[symbols.clone()])?;
+        }
+
+        let encoded = encoder.encode()?;
+        let consistent = recovery
+            .iter()
+            .zip(encoded.recovery_iter())
+            .all(|(shard, symbol)| &shard.as_ref()[symbols.clone()] == symbol);
+        if !consistent {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,98 @@
+//! Erasure coding of exported segments
+//!
+//! Every exported segment is a fixed W_G = 4104 bytes, coded into one small
+//! shard per validator. Validators store and ship the shards of many segments
+//! together (CE 139/140), so validator `v` holds a bundle
+//!
+//! ```text
+//! bundle[v] = shard_v(segment 0) ++ shard_v(segment 1) ++ ... ++ shard_v(segment S-1)
+//! ```
+//!
+//! where every segment shard has the same length, `segment_shard_len(params)`.
+
+use crate::erasure_codec::{decode_with_threads, encode_with_threads};
+use crate::error::ErasureError;
+use crate::params::ErasureParams;
+
+/// W_G - size of an exported segment in octets
+pub const SEGMENT_SIZE: usize = 4104;
+
+/// Bytes of one segment carried by each validator (W_P symbols)
+pub fn segment_shard_len(params: &ErasureParams) -> usize {
+    let symbol_size = params.symbol_size();
+    SEGMENT_SIZE
+        .div_ceil(params.original_shards())
+        .div_ceil(symbol_size)
+        * symbol_size
+}
+
+/// Encode segments into one shard bundle per validator
+pub fn encode_segments<S: AsRef<[u8]>>(
+    segments: &[S],
+    params: &ErasureParams,
+) -> Result<Vec<Vec<u8>>, ErasureError> {
+    let shard_len = segment_shard_len(params);
+    let mut bundles = vec![Vec::with_capacity(segments.len() * shard_len); params.total_shards()];
+
+    for segment in segments {
+        let segment = segment.as_ref();
+        if segment.len() != SEGMENT_SIZE {
+            return Err(ErasureError::WrongSegmentSize(segment.len()));
+        }
+
+        for (bundle, shard) in bundles
+            .iter_mut()
+            .zip(encode_with_threads(segment, params, 1)?)
+        {
+            bundle.extend_from_slice(&shard);
+        }
+    }
+
+    Ok(bundles)
+}
+
+/// Rebuild `segment_indices` from the bundles of at least `original_shards` validators
+///
+/// `bundles[i]` is the bundle of validator `validator_indices[i]`; all bundles
+/// must cover the same segments in the same order.
+pub fn decode_segments<B: AsRef<[u8]>>(
+    bundles: &[B],
+    validator_indices: &[usize],
+    segment_indices: &[usize],
+    params: &ErasureParams,
+) -> Result<Vec<Vec<u8>>, ErasureError> {
+    let shard_len = segment_shard_len(params);
+    let bundle_len = bundles.first().map_or(0, |bundle| bundle.as_ref().len());
+
+    for (bundle, &index) in bundles.iter().zip(validator_indices) {
+        let len = bundle.as_ref().len();
+        if len != bundle_len || len % shard_len != 0 {
+            return Err(ErasureError::WrongShardLength {
+                index,
+                expected: bundle_len - bundle_len % shard_len,
+                actual: len,
+            });
+        }
+    }
+
+    let segment_count = bundle_len / shard_len;
+    segment_indices
+        .iter()
+        .map(|&segment| {
+            if segment >= segment_count {
+                return Err(ErasureError::SegmentOutOfRange {
+                    segment,
+                    count: segment_count,
+                });
+            }
+
+            let range = segment * shard_len..(segment + 1) * shard_len;
+            let shards: Vec<&[u8]> = bundles
+                .iter()
+                .map(|bundle| &bundle.as_ref()[range.clone()])
+                .collect();
+
+            decode_with_threads(&shards, validator_indices, SEGMENT_SIZE, params, 1)
+        })
+        .collect()
+}
@@ -0,0 +1,182 @@
+//! Incremental erasure coding
+//!
+//! The original shards of the column-major layout are the (padded) input
+//! columns themselves, so the encoder copies pushed bytes straight into its
+//! preallocated shards. A row can be encoded as soon as its symbol in the last
+//! column has arrived; everything left, padding included, is encoded on finish.
+//! The final prefix of every shard can be drained while data is still arriving,
+//! so shards are sent out as they become ready.
+//!
+//! The decoder collects shards as peers deliver them and decodes once enough
+//! have arrived. Both produce exactly what `do_encode`/`do_decode` would.
+
+use crate::erasure_codec::{decode_with_threads, Config, Shard};
+use crate::error::ErasureError;
+use crate::params::ErasureParams;
+use reed_solomon_simd::ReedSolomonEncoder;
+
+pub struct StreamingEncoder {
+    config: Config,
+    data_len: usize,
+    written: usize,      // bytes pushed so far
+    encoded_rows: usize, // rows whose recovery symbols are final
+    drained: usize,      // shard prefix already handed out by `drain`
+    shards: Vec<Shard>,
+    encoder: ReedSolomonEncoder,
+}
+
+impl StreamingEncoder {
+    pub fn new(data_len: usize, params: &ErasureParams) -> Result<Self, ErasureError> {
+        let config = Config::new(data_len, params);
+        let encoder =
+            ReedSolomonEncoder::new(config.num_cols, config.recovery_count, config.symbol_size)?;
+        let shards = vec![vec![0u8; config.col_bytes]; config.num_cols + config.recovery_count];
+
+        Ok(Self {
+            config,
+            data_len,
+            written: 0,
+            encoded_rows: 0,
+            drained: 0,
+            shards,
+            encoder,
+        })
+    }
+
+    /// Append the next bytes of the data, encoding every row they complete
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), ErasureError> {
+        if self.written + chunk.len() > self.data_len {
+            return Err(ErasureError::TooMuchData {
+                expected: self.data_len,
+                got: self.written + chunk.len(),
+            });
+        }
+
+        // columns are contiguous in the input, so fill them in order
+        let mut rest = chunk;
+        while !rest.is_empty() {
+            let col = self.written / self.config.col_bytes;
+            let at = self.written % self.config.col_bytes;
+            let n = rest.len().min(self.config.col_bytes - at);
+
+            self.shards[col][at..at + n].copy_from_slice(&rest[..n]);
+            self.written += n;
+            rest = &rest[n..];
+        }
+
+        let last_col_start = (self.config.num_cols - 1) * self.config.col_bytes;
+        let ready_rows = self.written.saturating_sub(last_col_start) / self.config.symbol_size;
+        self.encode_rows_until(ready_rows)
+    }
+
+    /// Prefix length of every shard that is already final
+    pub fn encoded_bytes(&self) -> usize {
+        self.encoded_rows * self.config.symbol_size
+    }
+
+    /// The part of every shard that became final since the last drain
+    pub fn drain(&mut self) -> Vec<Shard> {
+        let ready = self.drained..self.encoded_bytes();
+        self.drained = ready.end;
+
+        self.shards
+            .iter()
+            .map(|shard| shard[ready.clone()].to_vec())
+            .collect()
+    }
+
+    /// Encode the remaining rows and hand out the rest of every shard, all of
+    /// it unless parts were already drained
+    pub fn finish(mut self) -> Result<Vec<Shard>, ErasureError> {
+        if self.written != self.data_len {
+            return Err(ErasureError::IncompleteData {
+                expected: self.data_len,
+                got: self.written,
+            });
+        }
+
+        self.encode_rows_until(self.config.num_rows)?;
+        if self.drained == 0 {
+            return Ok(self.shards);
+        }
+        Ok(self.drain())
+    }
+
+    fn encode_rows_until(&mut self, rows: usize) -> Result<(), ErasureError> {
+        let symbol_size = self.config.symbol_size;
+        let (originals, recovery) = self.shards.split_at_mut(self.config.num_cols);
+
+        for row in self.encoded_rows..rows {
+            let at = row * symbol_size..(row + 1) * symbol_size;
+
+            for original in originals.iter() {
+                self.encoder.add_original_shard(&original[at.clone()])?;
+            }
+
+            let encoded = self.encoder.encode()?;
+            for (shard, symbol) in recovery.iter_mut().zip(encoded.recovery_iter()) {
+                shard[at.clone()].copy_from_slice(symbol);
+            }
+        }
+
+        self.encoded_rows = self.encoded_rows.max(rows);
+        Ok(())
+    }
+}
+
+pub struct StreamingDecoder {
+    config: Config,
+    params: ErasureParams,
+    original_len: usize,
+    shards: Vec<Shard>,
+    indices: Vec<usize>,
+}
+
+impl StreamingDecoder {
+    pub fn new(original_len: usize, params: &ErasureParams) -> Self {
+        Self {
+            config: Config::new(original_len, params),
+            params: *params,
+            original_len,
+            shards: Vec::with_capacity(params.original_shards()),
+            indices: Vec::with_capacity(params.original_shards()),
+        }
+    }
+
+    /// Add the shard of validator `index`, returning whether decoding can start
+    pub fn add_shard(&mut self, index: usize, shard: &[u8]) -> Result<bool, ErasureError> {
+        let total = self.params.total_shards();
+        if index >= total {
+            return Err(ErasureError::IndexOutOfRange { index, total });
+        }
+        if self.indices.contains(&index) {
+            return Err(ErasureError::DuplicateIndex(index));
+        }
+        if shard.len() != self.config.col_bytes {
+            return Err(ErasureError::WrongShardLength {
+                index,
+                expected: self.config.col_bytes,
+                actual: shard.len(),
+            });
+        }
+
+        self.shards.push(shard.to_vec());
+        self.indices.push(index);
+        Ok(self.is_ready())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.indices.len() >= self.params.original_shards()
+    }
+
+    /// Decode from the shards received so far
+    pub fn decode(&self) -> Result<Vec<u8>, ErasureError> {
+        decode_with_threads(
+            &self.shards,
+            &self.indices,
+            self.original_len,
+            &self.params,
+            1,
+        )
+    }
+}
@@ -1,4 +1,16 @@
-use erasure_coding::erasure_codec::{do_decode, do_encode};
+use erasure_coding::erasure_codec::{
+    decode_into, decode_with_threads, do_decode, do_encode, encode_into, encode_with_threads,
+    shard_len,
+};
+use erasure_coding::erasure_root::{
+    blake2b_256, erasure_root, erasure_root_with_justifications, verify_shard,
+    well_balanced_merkle_root,
+};
+use erasure_coding::error::ErasureError;
+use erasure_coding::params::{ErasureParams, ParamsError};
+use erasure_coding::reconstruct::{reconstruct_shards, verify_shards};
+use erasure_coding::segments::{decode_segments, encode_segments, segment_shard_len, SEGMENT_SIZE};
+use erasure_coding::stream::{StreamingDecoder, StreamingEncoder};
 use std::error::Error;
 
 /// Create test data: [0, 1, 2, ..., size-1] (wrapping at 256)
@@ -11,7 +23,7 @@ fn test_encoding_produces_correct_shard_count() -> Result<(), Box<dyn Error>> {
     let data = test_data(100);
 
     // Tiny config: C=2 → V=6 shards
-    let shards = do_encode(data.clone(), 2)?;
+    let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
     assert_eq!(shards.len(), 6, "C=2 should produce V=6 shards");
 
     Ok(())
@@ -20,14 +32,14 @@ fn test_encoding_produces_correct_shard_count() -> Result<(), Box<dyn Error>> {
 #[test]
 fn test_roundtrip_with_all_original_shards() -> Result<(), Box<dyn Error>> {
     let data = test_data(32);
-    let shards = do_encode(data.clone(), 2)?;
+    let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
 
     // Use shards 0 and 1 (both original)
     let decoded = do_decode(
         vec![shards[0].clone(), shards[1].clone()],
         vec![0, 1],
         data.len(),
-        2,
+        &ErasureParams::tiny(),
     )?;
 
     assert_eq!(decoded, data, "Roundtrip with original shards failed");
@@ -37,14 +49,14 @@ fn test_roundtrip_with_all_original_shards() -> Result<(), Box<dyn Error>> {
 #[test]
 fn test_roundtrip_with_recovery_shards_only() -> Result<(), Box<dyn Error>> {
     let data = test_data(32);
-    let shards = do_encode(data.clone(), 2)?;
+    let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
 
     // Use shards 2 and 3 (both recovery)
     let decoded = do_decode(
         vec![shards[2].clone(), shards[3].clone()],
         vec![2, 3],
         data.len(),
-        2,
+        &ErasureParams::tiny(),
     )?;
 
     assert_eq!(decoded, data, "Roundtrip with recovery shards failed");
@@ -54,14 +66,14 @@ fn test_roundtrip_with_recovery_shards_only() -> Result<(), Box<dyn Error>> {
 #[test]
 fn test_roundtrip_with_mixed_shards() -> Result<(), Box<dyn Error>> {
     let data = test_data(32);
-    let shards = do_encode(data.clone(), 2)?;
+    let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
 
     // Use shard 0 (original) and shard 3 (recovery)
     let decoded = do_decode(
         vec![shards[0].clone(), shards[3].clone()],
         vec![0, 3],
         data.len(),
-        2,
+        &ErasureParams::tiny(),
     )?;
 
     assert_eq!(decoded, data, "Roundtrip with mixed shards failed");
@@ -74,13 +86,13 @@ fn test_roundtrip_various_sizes() -> Result<(), Box<dyn Error>> {
 
     for size in test_cases {
         let data = test_data(size);
-        let shards = do_encode(data.clone(), 2)?;
+        let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
 
         let decoded = do_decode(
             vec![shards[0].clone(), shards[1].clone()],
             vec![0, 1],
             data.len(),
-            2,
+            &ErasureParams::tiny(),
         )?;
 
         assert_eq!(decoded, data, "Roundtrip failed for size={}", size);
@@ -91,7 +103,7 @@ fn test_roundtrip_various_sizes() -> Result<(), Box<dyn Error>> {
 #[test]
 fn test_every_shard_combination() -> Result<(), Box<dyn Error>> {
     let data = test_data(20);
-    let shards = do_encode(data.clone(), 2)?;
+    let shards = do_encode(data.clone(), &ErasureParams::tiny())?;
 
     // Test all 15 possible pairs of 6 shards
     for i in 0..6 {
@@ -100,7 +112,7 @@ fn test_every_shard_combination() -> Result<(), Box<dyn Error>> {
                 vec![shards[i].clone(), shards[j].clone()],
                 vec![i, j],
                 data.len(),
-                2,
+                &ErasureParams::tiny(),
             )?;
 
             assert_eq!(decoded, data, "Failed with shards [{}, {}]", i, j);
@@ -108,3 +120,483 @@ fn test_every_shard_combination() -> Result<(), Box<dyn Error>> {
     }
     Ok(())
 }
+
+#[test]
+fn test_shard_count_follows_params() -> Result<(), Box<dyn Error>> {
+    let data = test_data(100);
+
+    for params in [ErasureParams::small(), ErasureParams::large()] {
+        let shards = do_encode(data.clone(), &params)?;
+        assert_eq!(shards.len(), params.total_shards());
+
+        // Recover from the last original_shards shards, all of them recovery shards
+        let first = params.total_shards() - params.original_shards();
+        let decoded = do_decode(
+            shards[first..].to_vec(),
+            (first..params.total_shards()).collect(),
+            data.len(),
+            &params,
+        )?;
+        assert_eq!(decoded, data, "Roundtrip failed for {:?}", params);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_named_params_match_validator_counts() {
+    assert_eq!(ErasureParams::tiny(), ErasureParams::new(2, 6, 2).unwrap());
+    assert_eq!(ErasureParams::full().original_shards(), 342);
+    assert_eq!(ErasureParams::full().recovery_shards(), 681);
+}
+
+#[test]
+fn test_inconsistent_params_are_rejected() {
+    assert_eq!(
+        ErasureParams::new(0, 6, 2),
+        Err(ParamsError::NoOriginalShards)
+    );
+    assert_eq!(
+        ErasureParams::new(6, 6, 2),
+        Err(ParamsError::NoRecoveryShards {
+            original: 6,
+            total: 6
+        })
+    );
+    assert_eq!(
+        ErasureParams::new(2, 6, 3),
+        Err(ParamsError::InvalidSymbolSize(3))
+    );
+    assert!(matches!(
+        ErasureParams::new(40_000, 65_000, 2),
+        Err(ParamsError::UnsupportedShardCount { .. })
+    ));
+}
+
+#[test]
+fn test_segment_bundles_concatenate_segment_shards() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let segments: Vec<Vec<u8>> = (0..3)
+        .map(|i| test_data(SEGMENT_SIZE + i)[i..].to_vec())
+        .collect();
+
+    let bundles = encode_segments(&segments, &params)?;
+    assert_eq!(bundles.len(), params.total_shards());
+
+    let shard_len = segment_shard_len(&params);
+    for (i, segment) in segments.iter().enumerate() {
+        let shards = do_encode(segment.clone(), &params)?;
+        for (bundle, shard) in bundles.iter().zip(shards) {
+            assert_eq!(bundle[i * shard_len..(i + 1) * shard_len], shard[..]);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_segment_roundtrip_picks_requested_segments() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::full();
+    assert_eq!(segment_shard_len(&params), 12);
+
+    let segments: Vec<Vec<u8>> = (0..4)
+        .map(|i| test_data(SEGMENT_SIZE + i)[i..].to_vec())
+        .collect();
+    let bundles = encode_segments(&segments, &params)?;
+
+    let validators: Vec<usize> = (500..500 + params.original_shards()).collect();
+    let selected: Vec<&Vec<u8>> = validators.iter().map(|&v| &bundles[v]).collect();
+
+    let decoded = decode_segments(&selected, &validators, &[3, 1], &params)?;
+    assert_eq!(decoded, vec![segments[3].clone(), segments[1].clone()]);
+    Ok(())
+}
+
+#[test]
+fn test_segments_must_be_segment_sized() {
+    assert!(encode_segments(&[vec![0u8; 10]], &ErasureParams::tiny()).is_err());
+
+    // Bundles only hold one segment, so segment 1 cannot be rebuilt
+    let bundles = encode_segments(&[vec![0u8; SEGMENT_SIZE]], &ErasureParams::tiny()).unwrap();
+    assert!(decode_segments(&bundles[..2], &[0, 1], &[1], &ErasureParams::tiny()).is_err());
+}
+
+#[test]
+fn test_well_balanced_merkle_root_splits_at_half() {
+    let node = |left: &[u8], right: &[u8]| blake2b_256(&[b"node", left, right].concat());
+    let (a, b, c) = (vec![1u8; 64], vec![2u8; 64], vec![3u8; 64]);
+
+    assert_eq!(well_balanced_merkle_root(&[&a]), blake2b_256(&a));
+    assert_eq!(
+        well_balanced_merkle_root(&[&a, &b, &c]),
+        node(&node(&a, &b), &c)
+    );
+}
+
+#[test]
+fn test_every_shard_verifies_against_erasure_root() -> Result<(), Box<dyn Error>> {
+    for params in [ErasureParams::tiny(), ErasureParams::medium()] {
+        let bundle_shards = do_encode(test_data(1000), &params)?;
+        let segments = vec![test_data(SEGMENT_SIZE), vec![0u8; SEGMENT_SIZE]];
+        let segment_bundles = encode_segments(&segments, &params)?;
+
+        let (root, justifications) =
+            erasure_root_with_justifications(&bundle_shards, &segment_bundles, &params)?;
+        assert_eq!(
+            root,
+            erasure_root(&bundle_shards, &segment_bundles, &params)?
+        );
+
+        for v in 0..params.total_shards() {
+            let (bundle, segments) = (&bundle_shards[v], &segment_bundles[v]);
+            assert!(verify_shard(
+                &root,
+                v,
+                bundle,
+                segments,
+                &justifications[v],
+                &params
+            ));
+
+            // the same shards do not verify for another validator
+            let other = (v + 1) % params.total_shards();
+            assert!(!verify_shard(
+                &root,
+                other,
+                bundle,
+                segments,
+                &justifications[v],
+                &params
+            ));
+        }
+
+        let mut tampered = bundle_shards[0].clone();
+        tampered[0] ^= 1;
+        let segments = &segment_bundles[0];
+        assert!(!verify_shard(
+            &root,
+            0,
+            &tampered,
+            segments,
+            &justifications[0],
+            &params
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_erasure_root_needs_one_shard_per_validator() {
+    let params = ErasureParams::tiny();
+    let shards = vec![vec![0u8; 2]; 5];
+    assert!(erasure_root(&shards, &shards, &params).is_err());
+}
+
+#[test]
+fn test_decode_rejects_bad_shard_sets() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let data = test_data(32);
+    let shards = do_encode(data.clone(), &params)?;
+    let decode = |shards: Vec<Vec<u8>>, indices: Vec<usize>| {
+        do_decode(shards, indices, data.len(), &params).unwrap_err()
+    };
+
+    assert!(matches!(
+        decode(vec![shards[0].clone(), shards[1].clone()], vec![0]),
+        ErasureError::MismatchedCounts {
+            shards: 2,
+            indices: 1
+        }
+    ));
+    assert!(matches!(
+        decode(vec![shards[0].clone()], vec![0]),
+        ErasureError::TooFewShards { needed: 2, got: 1 }
+    ));
+    assert!(matches!(
+        decode(vec![shards[0].clone(), shards[1].clone()], vec![0, 6]),
+        ErasureError::IndexOutOfRange { index: 6, total: 6 }
+    ));
+    assert!(matches!(
+        decode(vec![shards[3].clone(), shards[3].clone()], vec![3, 3]),
+        ErasureError::DuplicateIndex(3)
+    ));
+    assert!(matches!(
+        decode(vec![shards[0].clone(), shards[4][1..].to_vec()], vec![0, 4]),
+        ErasureError::WrongShardLength {
+            index: 4,
+            expected: 16,
+            actual: 15
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_decode_accepts_more_shards_than_needed() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let data = test_data(32);
+    let shards = do_encode(data.clone(), &params)?;
+
+    let decoded = do_decode(shards[1..].to_vec(), (1..6).collect(), data.len(), &params)?;
+    assert_eq!(decoded, data);
+    Ok(())
+}
+
+#[test]
+fn test_parallel_coding_is_byte_identical() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::full();
+    // ~300 rows per column, enough for several threads
+    let data = test_data(params.original_shards() * 600 + 7);
+
+    let sequential = do_encode(data.clone(), &params)?;
+    for threads in [2, 3, 8] {
+        assert_eq!(encode_with_threads(&data, &params, threads)?, sequential);
+    }
+
+    let indices: Vec<usize> = (400..400 + params.original_shards()).collect();
+    let shards: Vec<&[u8]> = indices.iter().map(|&i| sequential[i].as_slice()).collect();
+    for threads in [1, 4] {
+        let decoded = decode_with_threads(&shards, &indices, data.len(), &params, threads)?;
+        assert_eq!(decoded, data);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_empty_data_roundtrip() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let shards = encode_with_threads(&[], &params, 4)?;
+    assert!(shards.iter().all(|shard| shard.is_empty()));
+
+    let decoded = decode_with_threads(&shards[..2], &[0, 1], 0, &params, 4)?;
+    assert!(decoded.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_streaming_encoder_matches_do_encode() -> Result<(), Box<dyn Error>> {
+    for (params, size) in [
+        (ErasureParams::tiny(), 1001),
+        (ErasureParams::full(), 10_000),
+        (ErasureParams::full(), 100),
+    ] {
+        let data = test_data(size);
+        let expected = do_encode(data.clone(), &params)?;
+
+        for chunk_size in [1, 7, 512, size] {
+            let mut encoder = StreamingEncoder::new(data.len(), &params)?;
+            for chunk in data.chunks(chunk_size) {
+                encoder.push(chunk)?;
+            }
+            assert_eq!(encoder.finish()?, expected, "chunk size {}", chunk_size);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_streaming_encoder_finishes_rows_as_last_column_arrives() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let data = test_data(64);
+    let mut encoder = StreamingEncoder::new(data.len(), &params)?;
+
+    // column 0 alone completes no row
+    encoder.push(&data[..32])?;
+    assert_eq!(encoder.encoded_bytes(), 0);
+
+    encoder.push(&data[32..41])?;
+    assert_eq!(encoder.encoded_bytes(), 8);
+
+    assert!(matches!(
+        encoder.push(&data),
+        Err(ErasureError::TooMuchData { expected: 64, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_streaming_encoder_drains_final_rows() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let data = test_data(1001);
+    let expected = do_encode(data.clone(), &params)?;
+
+    let mut encoder = StreamingEncoder::new(data.len(), &params)?;
+    let mut shards = vec![Vec::new(); params.total_shards()];
+    for chunk in data.chunks(97) {
+        encoder.push(chunk)?;
+        let drained = encoder.drain();
+        assert!(drained.iter().all(|part| part.len() == drained[0].len()));
+        for (shard, part) in shards.iter_mut().zip(drained) {
+            shard.extend_from_slice(&part);
+        }
+        assert_eq!(shards[0].len(), encoder.encoded_bytes());
+    }
+
+    for (shard, rest) in shards.iter_mut().zip(encoder.finish()?) {
+        shard.extend_from_slice(&rest);
+    }
+    assert_eq!(shards, expected);
+    Ok(())
+}
+
+#[test]
+fn test_streaming_encoder_rejects_incomplete_data() -> Result<(), Box<dyn Error>> {
+    let mut encoder = StreamingEncoder::new(10, &ErasureParams::tiny())?;
+    encoder.push(&[1, 2, 3])?;
+    assert!(matches!(
+        encoder.finish(),
+        Err(ErasureError::IncompleteData {
+            expected: 10,
+            got: 3
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_streaming_decoder_collects_shards() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::medium();
+    let data = test_data(1000);
+    let shards = do_encode(data.clone(), &params)?;
+
+    let mut decoder = StreamingDecoder::new(data.len(), &params);
+    assert!(matches!(
+        decoder.add_shard(3, &shards[3][1..]),
+        Err(ErasureError::WrongShardLength { index: 3, .. })
+    ));
+
+    let arrivals: Vec<usize> = (0..params.total_shards()).rev().step_by(2).collect();
+    for (n, &index) in arrivals.iter().take(params.original_shards()).enumerate() {
+        assert!(matches!(
+            decoder.decode(),
+            Err(ErasureError::TooFewShards { .. })
+        ));
+        let ready = decoder.add_shard(index, &shards[index])?;
+        assert_eq!(ready, n + 1 == params.original_shards());
+    }
+
+    assert!(matches!(
+        decoder.add_shard(arrivals[0], &shards[arrivals[0]]),
+        Err(ErasureError::DuplicateIndex(_))
+    ));
+    assert_eq!(decoder.decode()?, data);
+    Ok(())
+}
+
+#[test]
+fn test_reconstruct_requested_shards() -> Result<(), Box<dyn Error>> {
+    for params in [ErasureParams::tiny(), ErasureParams::large()] {
+        let data = test_data(777);
+        let shards = do_encode(data.clone(), &params)?;
+
+        // hold the last original_shards shards, all recovery ones
+        let first = params.total_shards() - params.original_shards();
+        let indices: Vec<usize> = (first..params.total_shards()).collect();
+        let held = &shards[first..];
+
+        let wanted = [0, first - 1, params.total_shards() - 1, 1];
+        let rebuilt = reconstruct_shards(held, &indices, &wanted, data.len(), &params)?;
+        let expected: Vec<_> = wanted.iter().map(|&i| shards[i].clone()).collect();
+        assert_eq!(rebuilt, expected, "{:?}", params);
+
+        // originals only, so no re-encoding is needed
+        let rebuilt = reconstruct_shards(held, &indices, &[1, 0], data.len(), &params)?;
+        assert_eq!(rebuilt, vec![shards[1].clone(), shards[0].clone()]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reconstruct_rejects_unknown_shards() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let shards = do_encode(test_data(10), &params)?;
+
+    assert!(matches!(
+        reconstruct_shards(&shards[..2], &[0, 1], &[6], 10, &params),
+        Err(ErasureError::IndexOutOfRange { index: 6, total: 6 })
+    ));
+    assert!(matches!(
+        reconstruct_shards(&shards[..1], &[0], &[3], 10, &params),
+        Err(ErasureError::TooFewShards { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_verify_shards_detects_inconsistent_codewords() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::small();
+    let mut shards = do_encode(test_data(300), &params)?;
+    assert!(verify_shards(&shards, &params)?);
+
+    shards[7][5] ^= 1;
+    assert!(!verify_shards(&shards, &params)?);
+
+    shards[7].pop();
+    assert!(matches!(
+        verify_shards(&shards, &params),
+        Err(ErasureError::WrongShardLength { index: 7, .. })
+    ));
+    assert!(matches!(
+        verify_shards(&shards[1..], &params),
+        Err(ErasureError::WrongShardCount { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_encode_into_matches_separate_shards() -> Result<(), Box<dyn Error>> {
+    for (size, threads) in [(0, 1), (1, 1), (1000, 1), (100_000, 4)] {
+        let params = ErasureParams::large();
+        let data = test_data(size);
+        let shards = do_encode(data.clone(), &params)?;
+
+        let len = shard_len(size, &params);
+        let mut out = vec![0xffu8; len * params.total_shards()];
+        encode_into(&data, &params, threads, &mut out)?;
+        assert_eq!(out, shards.concat(), "size {}", size);
+
+        let mut columns = vec![0xffu8; len * params.original_shards()];
+        let indices: Vec<usize> = (params.recovery_shards()..params.total_shards()).collect();
+        decode_into(
+            &shards[params.recovery_shards()..],
+            &indices,
+            size,
+            &params,
+            threads,
+            &mut columns,
+        )?;
+        assert_eq!(&columns[..size], &data[..], "size {}", size);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_into_rejects_wrong_buffer_length() -> Result<(), Box<dyn Error>> {
+    let params = ErasureParams::tiny();
+    let data = test_data(100);
+    let len = shard_len(data.len(), &params);
+    let shards = do_encode(data.clone(), &params)?;
+
+    let encoded_len = len * params.total_shards();
+    let decoded_len = len * params.original_shards();
+    for delta in [-1isize, 1] {
+        let mut out = vec![0u8; encoded_len.saturating_add_signed(delta)];
+        assert!(
+            matches!(
+                encode_into(&data, &params, 1, &mut out),
+                Err(ErasureError::WrongOutputLength { .. })
+            ),
+            "encode_into with {} bytes",
+            out.len()
+        );
+
+        let mut columns = vec![0u8; decoded_len.saturating_add_signed(delta)];
+        assert!(
+            matches!(
+                decode_into(&shards[..2], &[0, 1], data.len(), &params, 1, &mut columns),
+                Err(ErasureError::WrongOutputLength { .. })
+            ),
+            "decode_into with {} bytes",
+            columns.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use erasure_coding::erasure_codec::{do_decode, do_encode, encode_with_threads};
+use erasure_coding::params::ErasureParams;
+use erasure_coding::stream::StreamingEncoder;
+use quickcheck::{QuickCheck, TestResult};
+
+const CONFIGS: [ErasureParams; 3] = [
+    ErasureParams::tiny(),
+    ErasureParams::small(),
+    ErasureParams::large(),
+];
+
+/// `count` distinct shard indices below `total`, shuffled by `picks`
+fn subset(picks: &[usize], total: usize, count: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..total).collect();
+    for (i, pick) in picks.iter().cycle().take(count).enumerate() {
+        indices.swap(i, i + pick % (total - i));
+    }
+    indices.truncate(count);
+    indices
+}
+
+fn config(choice: u8) -> ErasureParams {
+    CONFIGS[choice as usize % CONFIGS.len()]
+}
+
+#[test]
+fn prop_any_original_shards_subset_roundtrips() {
+    fn prop(data: Vec<u8>, picks: Vec<usize>, choice: u8) -> TestResult {
+        let params = config(choice);
+        let shards = do_encode(data.clone(), &params).unwrap();
+
+        let indices = subset(&picks, params.total_shards(), params.original_shards());
+        let selected = indices.iter().map(|&i| shards[i].clone()).collect();
+
+        TestResult::from_bool(do_decode(selected, indices, data.len(), &params).unwrap() == data)
+    }
+
+    QuickCheck::new()
+        .tests(200)
+        .quickcheck(prop as fn(Vec<u8>, Vec<usize>, u8) -> TestResult);
+}
+
+#[test]
+fn prop_encode_is_deterministic() {
+    fn prop(data: Vec<u8>, threads: u8, chunk_size: u8, choice: u8) -> bool {
+        let params = config(choice);
+        let shards = do_encode(data.clone(), &params).unwrap();
+
+        let mut encoder = StreamingEncoder::new(data.len(), &params).unwrap();
+        for chunk in data.chunks(chunk_size as usize + 1) {
+            encoder.push(chunk).unwrap();
+        }
+
+        shards == do_encode(data.clone(), &params).unwrap()
+            && shards == encode_with_threads(&data, &params, threads as usize % 8 + 1).unwrap()
+            && shards == encoder.finish().unwrap()
+    }
+
+    QuickCheck::new()
+        .tests(200)
+        .quickcheck(prop as fn(Vec<u8>, u8, u8, u8) -> bool);
+}
+
+#[test]
+fn prop_shards_have_equal_length() {
+    fn prop(data: Vec<u8>, choice: u8) -> bool {
+        let params = config(choice);
+        let shards = do_encode(data, &params).unwrap();
+
+        shards.len() == params.total_shards()
+            && shards.iter().all(|shard| shard.len() == shards[0].len())
+            && shards[0].len().is_multiple_of(params.symbol_size())
+    }
+
+    QuickCheck::new().quickcheck(prop as fn(Vec<u8>, u8) -> bool);
+}
@@ -0,0 +1,96 @@
+//! Conformance with the JAM erasure-coding test vectors
+//!
+//! The vectors are the `erasure/{tiny,full}` sets of jam-test-vectors, vendored
+//! under `tests/vectors` by `tests/vectors/update.sh`; set `JAM_TEST_VECTORS` to a
+//! checkout to run another revision. Missing vectors fail the tests rather than
+//! letting them pass unchecked.
+
+use erasure_coding::erasure_codec::{do_decode, do_encode};
+use erasure_coding::params::ErasureParams;
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+struct TestVector {
+    name: String,
+    data: Vec<u8>,
+    shards: Vec<Vec<u8>>,
+}
+
+fn vectors_dir() -> PathBuf {
+    match std::env::var_os("JAM_TEST_VECTORS") {
+        Some(checkout) => PathBuf::from(checkout).join("erasure"),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/vectors"),
+    }
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>, Box<dyn Error>> {
+    let hex_str = value.as_str().ok_or("expected a hex string")?;
+    Ok(hex::decode(hex_str.trim_start_matches("0x"))?)
+}
+
+/// All vectors of one configuration
+fn load_vectors(config: &str) -> Result<Vec<TestVector>, Box<dyn Error>> {
+    let dir = vectors_dir().join(config);
+    assert!(
+        dir.is_dir(),
+        "{} not found, vendor the vectors with tests/vectors/update.sh",
+        dir.display()
+    );
+
+    let mut vectors = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+
+        let json: Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let shards = json["shards"]
+            .as_array()
+            .ok_or("expected a shards array")?
+            .iter()
+            .map(decode_hex)
+            .collect::<Result<_, _>>()?;
+
+        vectors.push(TestVector {
+            name: path.display().to_string(),
+            data: decode_hex(&json["data"])?,
+            shards,
+        });
+    }
+
+    assert!(!vectors.is_empty(), "no vectors in {}", dir.display());
+    Ok(vectors)
+}
+
+fn check_vectors(config: &str, params: ErasureParams) -> Result<(), Box<dyn Error>> {
+    for vector in load_vectors(config)? {
+        let shards = do_encode(vector.data.clone(), &params)?;
+        assert_eq!(shards, vector.shards, "encode {}", vector.name);
+
+        // recover from the recovery shards only and from the originals only
+        let count = params.original_shards();
+        let first = params.total_shards() - count;
+        for indices in [
+            (first..first + count).collect::<Vec<_>>(),
+            (0..count).collect(),
+        ] {
+            let selected = indices.iter().map(|&i| vector.shards[i].clone()).collect();
+            let decoded = do_decode(selected, indices, vector.data.len(), &params)?;
+            assert_eq!(decoded, vector.data, "decode {}", vector.name);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_tiny_vectors() -> Result<(), Box<dyn Error>> {
+    check_vectors("tiny", ErasureParams::tiny())
+}
+
+#[test]
+fn test_full_vectors() -> Result<(), Box<dyn Error>> {
+    check_vectors("full", ErasureParams::full())
+}
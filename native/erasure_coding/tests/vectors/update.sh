@@ -0,0 +1,15 @@
+#!/bin/sh
+# Vendors the erasure-coding vectors of a jam-test-vectors checkout:
+#   tests/vectors/update.sh /path/to/jam-test-vectors
+set -eu
+
+checkout=${1:?usage: $0 /path/to/jam-test-vectors}
+here=$(dirname "$0")
+
+for config in tiny full; do
+    rm -rf "$here/$config"
+    mkdir -p "$here/$config"
+    cp "$checkout/erasure/$config"/*.json "$here/$config/"
+done
+
+git -C "$checkout" rev-parse HEAD > "$here/REVISION"
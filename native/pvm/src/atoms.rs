@@ -21,6 +21,17 @@ rustler::atoms! {
     stack_underflow,
     heap_overflow,
 
+    // execute options
+    trace,
+    max_steps,
+    pc_range,
+    opcodes,
+    profile,
+
+    // snapshot errors
+    invalid_snapshot,
+    snapshot_mismatch,
+
     // VM results
     halt,
     out_of_gas,
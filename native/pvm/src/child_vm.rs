@@ -1,12 +1,18 @@
 use crate::atoms;
 use crate::nif_types::{Registers, VmState};
+use crate::snapshot::{add_region, Snapshot, SnapshotKind};
 use pvm_core::{ChildVmInstance, ExecutionResult, Registers as CoreRegisters};
 use rustler::{Binary, Decoder, Encoder, Env, NifResult, ResourceArc, Term};
+use std::ops::Range;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 
 pub struct ChildVmResource {
     pub instance: Mutex<ChildVmInstance>,
+    // kept so that snapshots can rebuild the instance
+    pub program_blob: Vec<u8>,
+    // pages ever given access, the only ones a snapshot has to scan
+    pub regions: Mutex<Vec<Range<usize>>>,
 }
 
 impl rustler::Resource for ChildVmResource {}
@@ -49,6 +55,8 @@ pub fn create_instance<'a>(
     // Wrap in resource
     let resource = ResourceArc::new(ChildVmResource {
         instance: Mutex::new(instance),
+        program_blob: program_blob.as_slice().to_vec(),
+        regions: Mutex::new(Vec::new()),
     });
 
     Ok((atoms::ok(), resource).encode(env))
@@ -188,7 +196,16 @@ pub fn set_memory_access<'a>(
     };
 
     match instance.set_memory_access(page_idx, page_cnt, perm) {
-        Ok(()) => Ok(atoms::ok().encode(env)),
+        Ok(()) => {
+            if perm_val != 0 {
+                let mut regions = resource
+                    .regions
+                    .lock()
+                    .map_err(|_| rustler::Error::Term(Box::new(atoms::mutex_poisoned())))?;
+                add_region(&mut regions, page_idx..page_idx.saturating_add(page_cnt));
+            }
+            Ok(atoms::ok().encode(env))
+        }
         Err(()) => Ok((atoms::error(), atoms::panic()).encode(env)),
     }
 }
@@ -248,3 +265,61 @@ pub fn zero_memory<'a>(
         Err(_) => Ok((atoms::error(), atoms::oob()).encode(env)),
     }
 }
+
+/// Serialize a child VM: its program, state and memory
+pub fn snapshot<'a>(env: Env<'a>, instance_ref: Term<'a>) -> NifResult<Term<'a>> {
+    let resource: ResourceArc<ChildVmResource> = ResourceArc::decode(instance_ref)?;
+    let instance = resource
+        .instance
+        .lock()
+        .map_err(|_| rustler::Error::Term(Box::new(atoms::mutex_poisoned())))?;
+
+    let regions = resource
+        .regions
+        .lock()
+        .map_err(|_| rustler::Error::Term(Box::new(atoms::mutex_poisoned())))?;
+
+    let state = VmState::from(instance.get_state().clone());
+    let bytes = Snapshot::capture(
+        SnapshotKind::ChildVm,
+        &resource.program_blob,
+        state,
+        &*instance,
+        &regions,
+    )
+    .to_bytes();
+
+    let mut owned_binary = rustler::OwnedBinary::new(bytes.len()).unwrap();
+    owned_binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok((atoms::ok(), Binary::from_owned(owned_binary, env)).encode(env))
+}
+
+/// Rebuild a child VM from a snapshot as a new instance
+pub fn restore<'a>(env: Env<'a>, snapshot: Binary<'a>) -> NifResult<Term<'a>> {
+    let snapshot = Snapshot::parse(snapshot.as_slice(), SnapshotKind::ChildVm)
+        .ok_or_else(|| rustler::Error::Term(Box::new(atoms::invalid_snapshot())))?;
+    let state = snapshot.state;
+
+    let mut instance = ChildVmInstance::new(
+        generate_instance_id(),
+        &snapshot.program,
+        state.pc,
+        state.initial_gas,
+        CoreRegisters::from(state.registers),
+    )
+    .map_err(|_| rustler::Error::Term(Box::new(atoms::invalid_program())))?;
+    instance.get_state_mut().spent_gas = state.spent_gas;
+
+    if !snapshot.restore_memory(&mut instance) {
+        return Err(rustler::Error::Term(Box::new(atoms::invalid_snapshot())));
+    }
+
+    let regions = snapshot.regions();
+    let resource = ResourceArc::new(ChildVmResource {
+        instance: Mutex::new(instance),
+        program_blob: snapshot.program,
+        regions: Mutex::new(regions),
+    });
+
+    Ok((atoms::ok(), resource).encode(env))
+}
@@ -1,30 +1,72 @@
+use crate::instrumentation::Instrumentation;
+use crate::program_cache::CodeHash;
 use pvm_core::VmContext;
-use std::collections::HashMap;
+use rustler::{Resource, ResourceArc};
 use std::sync::{
-    atomic::{AtomicU64, Ordering},
-    Arc, LazyLock, Mutex,
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
 };
 
-static NEXT_CTX_ID: AtomicU64 = AtomicU64::new(1);
+/// Contexts of programs that have not finished yet, for metrics
+static LIVE_CONTEXTS: AtomicUsize = AtomicUsize::new(0);
 
-static VM_CONTEXTS: LazyLock<Mutex<HashMap<u64, Arc<VmContext>>>> =
-    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// The context token handed to Elixir while a program is suspended on a host call.
+/// The context is released when the program finishes, or when the token is
+/// garbage collected after the invocation was abandoned.
+pub struct ContextResource {
+    context: Mutex<Option<Arc<VmContext>>>,
+    code_hash: CodeHash,
+    // the invocation's trace and profile, carried across host calls
+    pub instrumentation: Mutex<Instrumentation>,
+}
+
+impl Resource for ContextResource {}
+
+pub type ContextRef = ResourceArc<ContextResource>;
+
+impl ContextResource {
+    pub fn new_ref(
+        context: Arc<VmContext>,
+        code_hash: CodeHash,
+        instrumentation: Instrumentation,
+    ) -> ContextRef {
+        LIVE_CONTEXTS.fetch_add(1, Ordering::Relaxed);
+        ResourceArc::new(Self {
+            context: Mutex::new(Some(context)),
+            code_hash,
+            instrumentation: Mutex::new(instrumentation),
+        })
+    }
+
+    /// Hash of the code the context was deblobbed from
+    pub fn code_hash(&self) -> CodeHash {
+        self.code_hash
+    }
+
+    fn take(&self) -> Option<Arc<VmContext>> {
+        let taken = self.context.lock().ok()?.take();
+        if taken.is_some() {
+            LIVE_CONTEXTS.fetch_sub(1, Ordering::Relaxed);
+        }
+        taken
+    }
+}
 
-pub fn generate_context_token() -> u64 {
-    NEXT_CTX_ID.fetch_add(1, Ordering::Relaxed)
+impl Drop for ContextResource {
+    fn drop(&mut self) {
+        self.take();
+    }
 }
 
-pub fn store_context(token: u64, context: Arc<VmContext>) {
-    let mut contexts = VM_CONTEXTS.lock().unwrap();
-    contexts.insert(token, context);
+pub fn get_context(token: &ContextRef) -> Option<Arc<VmContext>> {
+    token.context.lock().ok()?.clone()
 }
 
-pub fn get_context(token: u64) -> Option<Arc<VmContext>> {
-    let contexts = VM_CONTEXTS.lock().unwrap();
-    contexts.get(&token).cloned()
+/// Free the context once its program has finished; resuming it afterwards fails
+pub fn release_context(token: &ContextRef) {
+    token.take();
 }
 
-pub fn remove_context(token: u64) {
-    let mut contexts = VM_CONTEXTS.lock().unwrap();
-    contexts.remove(&token);
+pub fn live_contexts() -> usize {
+    LIVE_CONTEXTS.load(Ordering::Relaxed)
 }
@@ -1,16 +1,35 @@
-use crate::context::{generate_context_token, get_context, remove_context, store_context};
+use crate::context::{get_context, release_context, ContextRef, ContextResource};
 use crate::memory::{get_owned, put_owned, MemoryError, MemoryRef, MemoryResource};
 use crate::{
     atoms,
+    instrumentation::{instrumentation_from_options, Instrumentation},
     nif_types::{ExecuteResult, HostOutput, VmState},
+    program_cache::{self, CodeHash},
+    snapshot::{invocation_regions, Snapshot, SnapshotKind},
+    trace::Trace,
 };
-use pvm_core::vm::tracer::Tracer;
 use pvm_core::{deblob, ExecutionResult, Vm, VmContext, VmState as CoreVmState};
-use rustler::{Binary, Decoder, Encoder, Env, LocalPid, NifResult, Term};
+use rustler::{Binary, Decoder, Encoder, Env, LocalPid, NifResult, OwnedBinary, Term};
 use std::sync::Arc;
 
-fn execute<'a>(env: Env<'a>, mut vm: Vm, context_token: u64) -> NifResult<ExecuteResult<'a>> {
-    let result = vm.execute();
+fn execute<'a>(
+    env: Env<'a>,
+    vm: Vm,
+    context: &Arc<VmContext>,
+    context_token: ContextRef,
+) -> NifResult<ExecuteResult<'a>> {
+    let mut instrumentation = context_token
+        .instrumentation
+        .lock()
+        .map_err(|_| rustler::Error::Term(Box::new(atoms::mutex_poisoned())))?;
+    let (result, mut vm) = instrumentation.run(vm, context);
+    let trace_records = instrumentation.trace.as_mut().map(Trace::take_records);
+    let profile = instrumentation
+        .profile
+        .as_ref()
+        .map(|profile| profile.to_map());
+    drop(instrumentation);
+
     let used_gas = vm.get_state().spent_gas;
 
     let output_bytes = match result {
@@ -30,27 +49,30 @@ fn execute<'a>(env: Env<'a>, mut vm: Vm, context_token: u64) -> NifResult<Execut
 
     match result {
         ExecutionResult::HostCall { call_id } => {
-            handle_host_call(env, vm, call_id, context_token)?;
+            handle_host_call(env, vm, call_id, context_token.clone())?;
         }
         _ => {
-            remove_context(context_token);
+            release_context(&context_token);
         }
     }
 
-    Ok(ExecuteResult::from_core_result(
-        env,
-        result,
-        used_gas,
-        context_token,
-        output_bytes,
-    ))
+    let mut execute_result =
+        ExecuteResult::from_core_result(env, result, used_gas, Some(context_token), output_bytes);
+    execute_result.trace = trace_records.map(|records| {
+        let mut owned_binary = OwnedBinary::new(records.len()).unwrap();
+        owned_binary.as_mut_slice().copy_from_slice(&records);
+        Binary::from_owned(owned_binary, env)
+    });
+    execute_result.profile = profile;
+
+    Ok(execute_result)
 }
 
 fn handle_host_call<'a>(
     env: Env<'a>,
     mut vm: Vm,
     call_id: u64,
-    context_token: u64,
+    context_token: ContextRef,
 ) -> NifResult<()> {
     if let Some(memory) = vm.take_memory() {
         let memory_ref = MemoryResource::new_ref();
@@ -82,30 +104,34 @@ pub fn execute_program<'a>(
     pc_term: Term<'a>,
     gas_term: Term<'a>,
     args_term: Term<'a>,
+    options_term: Term<'a>,
 ) -> NifResult<ExecuteResult<'a>> {
     let linked_program: Binary<'a> = Binary::decode(program_term)?;
     let pc: usize = usize::decode(pc_term)?;
     let gas: u64 = u64::decode(gas_term)?;
     let args: Binary<'a> = Binary::decode(args_term)?;
+    let instrumentation = instrumentation_from_options(options_term)?;
 
-    let (context, registers, memory) = match initialize_vm_context(&linked_program, &args) {
-        Some(init_data) => init_data,
-        None => {
-            return Ok(ExecuteResult {
-                used_gas: 0,
-                output: HostOutput::Atom(atoms::panic()),
-                context_token: 0,
-            });
-        }
-    };
+    let (context, code_hash, registers, memory) =
+        match initialize_vm_context(&linked_program, &args) {
+            Some(init_data) => init_data,
+            None => {
+                return Ok(ExecuteResult {
+                    used_gas: 0,
+                    output: HostOutput::Atom(atoms::panic()),
+                    context_token: None,
+                    trace: None,
+                    profile: None,
+                });
+            }
+        };
 
-    let token = generate_context_token();
-    store_context(token, context.clone());
+    let token = ContextResource::new_ref(context.clone(), code_hash, instrumentation);
 
     let state = CoreVmState::new(registers, pc, gas);
     let vm = Vm::new(context.clone(), state, Some(memory));
 
-    execute(env, vm, token)
+    execute(env, vm, &context, token)
 }
 
 pub fn resume_execution<'a>(
@@ -116,9 +142,9 @@ pub fn resume_execution<'a>(
 ) -> NifResult<ExecuteResult<'a>> {
     let new_state: VmState = VmState::decode(new_state_term)?;
     let memory_ref: MemoryRef = MemoryRef::decode(memory_ref_term)?;
-    let context_token: u64 = u64::decode(context_token_term)?;
+    let context_token: ContextRef = ContextRef::decode(context_token_term)?;
 
-    let context = get_context(context_token)
+    let context = get_context(&context_token)
         .ok_or_else(|| rustler::Error::Term(Box::new(atoms::no_vm_context())))?;
 
     // Convert NIF state to core state
@@ -133,34 +159,106 @@ pub fn resume_execution<'a>(
 
     let vm = Vm::new(context.clone(), core_state, memory);
 
-    execute(env, vm, context_token)
+    execute(env, vm, &context, context_token)
 }
 
 fn initialize_vm_context(
     linked_program: &[u8],
     args: &[u8],
-) -> Option<(Arc<VmContext>, pvm_core::Registers, pvm_core::Memory)> {
+) -> Option<(
+    Arc<VmContext>,
+    CodeHash,
+    pvm_core::Registers,
+    pvm_core::Memory,
+)> {
     let (code, registers, memory) = pvm_core::initialize_program(linked_program, args)?;
+    let (context, code_hash) = load_vm_context(&code)?;
 
-    let deblob_result = deblob(&code).ok()?;
+    Some((context, code_hash, registers, memory))
+}
 
-    let start_set = pvm_core::StartSet::build(&deblob_result.program, &deblob_result.bitmask);
+fn load_vm_context(code: &[u8]) -> Option<(Arc<VmContext>, CodeHash)> {
+    let code_hash = program_cache::code_hash(code);
+    let context = program_cache::get_or_build(code_hash, || build_vm_context(code))?;
 
-    let tracer = if std::env::var("PVM_TRACE").map(|v| v == "true").unwrap_or(false) {
-        Some(Tracer::new())
-    } else {
-        None
-    };
+    Some((context, code_hash))
+}
+
+fn build_vm_context(code: &[u8]) -> Option<Arc<VmContext>> {
+    let deblob_result = deblob(code).ok()?;
+
+    let start_set = pvm_core::StartSet::build(&deblob_result.program, &deblob_result.bitmask);
 
-    let context = Arc::new(VmContext {
+    Some(Arc::new(VmContext {
         program: deblob_result.program,
         bitmask: deblob_result.bitmask,
         jump_table: deblob_result.jump_table,
-        tracer: tracer,
+        tracer: None,
         start_set,
-    });
+    }))
+}
+
+/// Serialize a suspended invocation: its state, its memory and the hash of its code
+pub fn snapshot_invocation<'a>(
+    env: Env<'a>,
+    state: VmState,
+    memory_ref: MemoryRef,
+    context_token: ContextRef,
+) -> NifResult<Term<'a>> {
+    if get_context(&context_token).is_none() {
+        return Err(rustler::Error::Term(Box::new(atoms::no_vm_context())));
+    }
+
+    let memory_guard = memory_ref
+        .memory
+        .lock()
+        .map_err(|_| rustler::Error::Term(Box::new(atoms::mutex_poisoned())))?;
+    let memory = memory_guard
+        .as_ref()
+        .ok_or_else(|| rustler::Error::Term(Box::new(atoms::memory_not_available())))?;
+
+    let code_hash = context_token.code_hash();
+    let regions = invocation_regions(memory);
+    let bytes = Snapshot::capture(
+        SnapshotKind::Invocation,
+        &code_hash,
+        state,
+        memory,
+        &regions,
+    )
+    .to_bytes();
+
+    let mut owned_binary = OwnedBinary::new(bytes.len()).unwrap();
+    owned_binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok((atoms::ok(), Binary::from_owned(owned_binary, env)).encode(env))
+}
+
+/// Rebuild a suspended invocation of `linked_program` from its snapshot, returning
+/// `{state, memory_ref, context_token}` ready for `resume`
+pub fn restore_invocation<'a>(
+    env: Env<'a>,
+    snapshot: Binary<'a>,
+    linked_program: Binary<'a>,
+) -> NifResult<Term<'a>> {
+    let snapshot = Snapshot::parse(snapshot.as_slice(), SnapshotKind::Invocation)
+        .ok_or_else(|| rustler::Error::Term(Box::new(atoms::invalid_snapshot())))?;
+
+    let (context, code_hash) = pvm_core::initialize_program(&linked_program, &[])
+        .and_then(|(code, _, _)| load_vm_context(&code))
+        .ok_or_else(|| rustler::Error::Term(Box::new(atoms::invalid_program())))?;
+    if snapshot.program != code_hash {
+        return Err(rustler::Error::Term(Box::new(atoms::snapshot_mismatch())));
+    }
+
+    let mut memory = pvm_core::Memory::builder().build();
+    if !snapshot.restore_memory(&mut memory) {
+        return Err(rustler::Error::Term(Box::new(atoms::invalid_snapshot())));
+    }
+    let memory_ref = MemoryResource::new_ref();
+    let _ = put_owned(&memory_ref, memory);
 
-    Some((context, registers, memory))
+    let context_token = ContextResource::new_ref(context, code_hash, Instrumentation::default());
+    Ok((atoms::ok(), (snapshot.state, memory_ref, context_token)).encode(env))
 }
 
 /// Validate a program blob by attempting to deblob it.
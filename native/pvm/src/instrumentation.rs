@@ -0,0 +1,172 @@
+//! Single-stepped execution for tracing and profiling
+//!
+//! pvm_core has no stepping API, so each step runs the VM with just enough gas
+//! for one instruction: it stops out of gas on the following instruction,
+//! leaving the pc and registers right after the stepped one. The budget starts
+//! at what the instruction at that pc was last charged, or one unit, and grows a
+//! unit at a time until the pc moves, so the step is charged exactly what the
+//! instruction costs. A pc costs one run once it has been stepped, and as many
+//! runs as its cost the first time.
+//!
+//! A step ends where pvm_core next charges gas, so an instruction it charges
+//! nothing for gets no step of its own: it runs in the step of the charged
+//! instruction before it, whose record keeps its own pc and opcode while
+//! `next_pc` and `after` are those past the uncharged ones. pvm_core charges
+//! every instruction, terminators included, at least one unit, which the
+//! `tracing` tests of `test/pvm/native_test.exs` check, so each executed
+//! instruction is a step of its own; were it to charge per basic block, a step
+//! would be a whole block. Every step is handed to the invocation's trace and
+//! profile.
+
+use crate::atoms;
+use crate::opcodes;
+use crate::profile::Profile;
+use crate::trace::{trace_from_options, Trace};
+use pvm_core::{ExecutionResult, Vm, VmContext};
+use rustler::{NifResult, Term};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One executed instruction
+pub struct Step {
+    pub pc: u32,
+    pub opcode: u8,
+    pub register: usize, // the instruction's first register operand
+    pub gas: u64,        // remaining before the instruction
+    pub charged: u64,    // gas the instruction cost
+    pub next_pc: u32,
+    pub before: [u64; 13],
+    pub after: [u64; 13],
+}
+
+/// What an invocation collects, kept with its context token across host calls
+#[derive(Default)]
+pub struct Instrumentation {
+    pub trace: Option<Trace>,
+    pub profile: Option<Profile>,
+    // gas last charged at each pc, the first budget tried when stepping it again
+    costs: HashMap<usize, u64>,
+}
+
+/// Instrumentation requested by an `execute` options map
+pub fn instrumentation_from_options(options: Term) -> NifResult<Instrumentation> {
+    let profile = match options.map_get(atoms::profile()) {
+        Ok(term) => term.decode::<bool>()?.then(Profile::default),
+        Err(_) => None,
+    };
+
+    Ok(Instrumentation {
+        trace: trace_from_options(options)?,
+        profile,
+        costs: HashMap::new(),
+    })
+}
+
+impl Instrumentation {
+    fn observing(&self) -> bool {
+        self.profile.is_some() || self.trace.as_ref().is_some_and(|trace| !trace.exhausted())
+    }
+
+    /// Run `vm` to its next exit, stepping while anything still observes the steps
+    pub fn run(&mut self, mut vm: Vm, context: &Arc<VmContext>) -> (ExecutionResult, Vm) {
+        if let Some(profile) = self.profile.as_mut() {
+            profile.resumed(remaining_gas(&vm));
+        }
+        if !self.observing() {
+            return (vm.execute(), vm);
+        }
+
+        let mut costs = std::mem::take(&mut self.costs);
+        let (result, vm) = run_stepped(vm, context, &mut costs, |step| {
+            if let Some(trace) = self.trace.as_mut() {
+                trace.record(step);
+            }
+            if let Some(profile) = self.profile.as_mut() {
+                profile.record(step);
+            }
+            self.observing()
+        });
+        self.costs = costs;
+
+        if let (Some(profile), ExecutionResult::HostCall { call_id }) =
+            (self.profile.as_mut(), &result)
+        {
+            profile.suspended(*call_id, remaining_gas(&vm));
+        }
+        (result, vm)
+    }
+}
+
+fn remaining_gas(vm: &Vm) -> u64 {
+    let state = vm.get_state();
+    state.initial_gas.saturating_sub(state.spent_gas)
+}
+
+/// Step `vm` until it exits or `on_step` returns false, then run the rest at once
+fn run_stepped(
+    mut vm: Vm,
+    context: &Arc<VmContext>,
+    costs: &mut HashMap<usize, u64>,
+    mut on_step: impl FnMut(&Step) -> bool,
+) -> (ExecutionResult, Vm) {
+    let mut stepping = true;
+    loop {
+        let state = vm.get_state().clone();
+        let remaining = remaining_gas(&vm);
+        if !stepping || remaining == 0 {
+            let result = vm.execute();
+            return (result, vm);
+        }
+
+        let mut budget = costs
+            .get(&state.pc)
+            .copied()
+            .unwrap_or(1)
+            .clamp(1, remaining);
+        let mut memory = vm.take_memory();
+        let (result, mut step_vm, stalled) = loop {
+            let mut step_state = state.clone();
+            step_state.initial_gas = state.spent_gas + budget;
+            let mut step_vm = Vm::new(context.clone(), step_state, memory);
+            let result = step_vm.execute();
+
+            let stalled =
+                matches!(result, ExecutionResult::OutOfGas) && step_vm.get_state().pc == state.pc;
+            if !stalled || budget == remaining {
+                break (result, step_vm, stalled);
+            }
+            memory = step_vm.take_memory();
+            budget += 1;
+        };
+        if stalled {
+            // out of gas for good; given all the remaining gas the step VM is the real one
+            return (result, step_vm);
+        }
+
+        // restore the real gas: only the stepped instruction was paid for
+        let mut next = step_vm.get_state().clone();
+        let charged = next
+            .spent_gas
+            .min(state.spent_gas + budget)
+            .saturating_sub(state.spent_gas);
+        next.initial_gas = state.initial_gas;
+        next.spent_gas = state.spent_gas + charged;
+        costs.insert(state.pc, charged);
+
+        stepping = on_step(&Step {
+            pc: state.pc as u32,
+            opcode: context.program.get(state.pc).copied().unwrap_or(0),
+            register: opcodes::first_register(&context.program, state.pc),
+            gas: remaining,
+            charged,
+            next_pc: next.pc as u32,
+            before: state.registers.data,
+            after: next.registers.data,
+        });
+
+        vm = Vm::new(context.clone(), next, step_vm.take_memory());
+        if !matches!(result, ExecutionResult::OutOfGas) {
+            return (result, vm);
+        }
+    }
+}
@@ -2,16 +2,25 @@ pub mod atoms;
 pub mod child_vm;
 pub mod context;
 pub mod execution;
+pub mod instrumentation;
 pub mod memory;
 pub mod nif_functions;
 pub mod nif_types;
+pub mod opcodes;
+pub mod profile;
+pub mod program_cache;
+pub mod snapshot;
+pub mod trace;
 
 use crate::child_vm::ChildVmResource;
+use crate::context::ContextResource;
 use crate::memory::MemoryResource;
 use rustler::{Env, Term};
 
 rustler::init!("Elixir.Pvm.Native", load = load);
 
 fn load(env: Env, _info: Term) -> bool {
-    env.register::<MemoryResource>().is_ok() && env.register::<ChildVmResource>().is_ok()
+    env.register::<MemoryResource>().is_ok()
+        && env.register::<ChildVmResource>().is_ok()
+        && env.register::<ContextResource>().is_ok()
 }
@@ -1,7 +1,11 @@
 use crate::child_vm;
+use crate::context::ContextRef;
 use crate::execution::{execute_program, resume_execution};
 use crate::memory::{put_owned, MemoryRef, MemoryResource};
-use crate::{atoms, nif_types::ExecuteResult};
+use crate::{
+    atoms,
+    nif_types::{ExecuteResult, VmState},
+};
 use pvm_core::Memory as CoreMemory;
 use rustler::{nif, Atom, Binary, Encoder, Env, Error, NifResult, OwnedBinary, Term};
 
@@ -12,8 +16,16 @@ pub fn execute<'a>(
     pc_term: Term<'a>,
     gas_term: Term<'a>,
     args_term: Term<'a>,
+    options_term: Term<'a>,
 ) -> NifResult<ExecuteResult<'a>> {
-    execute_program(env, program_term, pc_term, gas_term, args_term)
+    execute_program(
+        env,
+        program_term,
+        pc_term,
+        gas_term,
+        args_term,
+        options_term,
+    )
 }
 
 #[nif(schedule = "DirtyCpu")]
@@ -26,6 +38,37 @@ pub fn resume<'a>(
     resume_execution(env, new_state_term, memory_ref_term, context_token_term)
 }
 
+/// Number of suspended programs whose context has not been freed yet
+#[nif]
+pub fn live_vm_contexts() -> usize {
+    crate::context::live_contexts()
+}
+
+/// Cumulative hits and misses of the deblobbed program cache
+#[nif]
+pub fn program_cache_stats() -> crate::program_cache::ProgramCacheStats {
+    crate::program_cache::stats()
+}
+
+#[nif(schedule = "DirtyCpu")]
+pub fn snapshot_invocation<'a>(
+    env: Env<'a>,
+    state: VmState,
+    memory_ref: MemoryRef,
+    context_token: ContextRef,
+) -> NifResult<Term<'a>> {
+    crate::execution::snapshot_invocation(env, state, memory_ref, context_token)
+}
+
+#[nif(schedule = "DirtyCpu")]
+pub fn restore_invocation<'a>(
+    env: Env<'a>,
+    snapshot: Binary<'a>,
+    linked_program: Binary<'a>,
+) -> NifResult<Term<'a>> {
+    crate::execution::restore_invocation(env, snapshot, linked_program)
+}
+
 #[nif(schedule = "DirtyCpu")]
 pub fn build_memory() -> MemoryRef {
     let memory_ref = MemoryResource::new_ref();
@@ -180,6 +223,15 @@ pub fn destroy_child_vm<'a>(env: Env<'a>, instance_ref: Term<'a>) -> NifResult<T
     child_vm::destroy(env, instance_ref)
 }
 
+#[nif(schedule = "DirtyCpu")]
+pub fn snapshot_child_vm<'a>(env: Env<'a>, instance_ref: Term<'a>) -> NifResult<Term<'a>> {
+    child_vm::snapshot(env, instance_ref)
+}
+#[nif(schedule = "DirtyCpu")]
+pub fn restore_child_vm<'a>(env: Env<'a>, snapshot: Binary<'a>) -> NifResult<Term<'a>> {
+    child_vm::restore(env, snapshot)
+}
+
 #[nif]
 pub fn set_child_vm_memory_access<'a>(
     env: Env<'a>,
@@ -1,4 +1,6 @@
 use crate::atoms;
+use crate::context::ContextRef;
+use crate::profile::ProfileMap;
 use pvm_core::{ExecutionResult, Registers as CoreRegisters, VmState as CoreVmState};
 use rustler::{Binary, Decoder, Encoder, Env, NifStruct, NifUntaggedEnum, OwnedBinary, Term};
 
@@ -13,7 +15,11 @@ pub enum HostOutput<'a> {
 pub struct ExecuteResult<'a> {
     pub used_gas: u64,
     pub output: HostOutput<'a>,
-    pub context_token: u64,
+    pub context_token: Option<ContextRef>,
+    // trace records of this run, when the invocation is traced
+    pub trace: Option<Binary<'a>>,
+    // gas profile of the invocation so far, when it is profiled
+    pub profile: Option<ProfileMap>,
 }
 
 impl<'a> ExecuteResult<'a> {
@@ -25,7 +31,7 @@ impl<'a> ExecuteResult<'a> {
         env: Env<'a>,
         core_result: ExecutionResult,
         used_gas: u64,
-        context_token: u64,
+        context_token: Option<ContextRef>,
         output_bytes: Option<Vec<u8>>,
     ) -> Self {
         let output = match core_result {
@@ -50,6 +56,8 @@ impl<'a> ExecuteResult<'a> {
             used_gas,
             output,
             context_token,
+            trace: None,
+            profile: None,
         }
     }
 }
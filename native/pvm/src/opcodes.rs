@@ -0,0 +1,38 @@
+//! Opcodes and operand decoding the instrumentation relies on (GP appendix A)
+
+pub const TRAP: u8 = 0;
+pub const FALLTHROUGH: u8 = 1;
+pub const JUMP: u8 = 40;
+pub const JUMP_IND: u8 = 50;
+pub const LOAD_IMM_JUMP: u8 = 80;
+pub const BRANCH_EQ_IMM: u8 = 81;
+pub const BRANCH_GT_S_IMM: u8 = 90;
+pub const BRANCH_EQ: u8 = 170;
+pub const BRANCH_GE_S: u8 = 175;
+pub const LOAD_IMM_JUMP_IND: u8 = 180;
+
+/// Opcodes ending a basic block
+pub fn is_terminator(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        TRAP | FALLTHROUGH
+            | JUMP
+            | JUMP_IND
+            | LOAD_IMM_JUMP..=BRANCH_GT_S_IMM
+            | BRANCH_EQ..=BRANCH_GE_S
+            | LOAD_IMM_JUMP_IND
+    )
+}
+
+/// Calls: jumps that first load a return address into their register operand
+pub fn is_call(opcode: u8) -> bool {
+    matches!(opcode, LOAD_IMM_JUMP | LOAD_IMM_JUMP_IND)
+}
+
+/// The register operand `r_A` of the instruction at `pc`: the low nibble of the
+/// byte after the opcode, capped at 12
+pub fn first_register(program: &[u8], pc: usize) -> usize {
+    program
+        .get(pc + 1)
+        .map_or(0, |&byte| usize::from(byte & 0x0f).min(12))
+}
@@ -0,0 +1,109 @@
+//! Gas profiles of single-stepped invocations
+//!
+//! The gas every step was charged is attributed to its basic block, opcode and
+//! call stack, and host calls to the gas they charged while the VM was suspended.
+//! Call frames are keyed by the pc they were entered at, i.e. the jump-table
+//! target of the call, and folded into `caller;callee gas` lines that flamegraph
+//! tools read directly.
+//!
+//! A call is a `load_imm_jump` or `load_imm_jump_ind`, which loads the return
+//! address into its register before jumping. A `jump_ind` through a register
+//! holding the return address of a pending call returns from it, and from any
+//! call made since; other indirect jumps stay within the frame.
+
+use crate::instrumentation::Step;
+use crate::opcodes::{self, JUMP_IND};
+use rustler::NifMap;
+use std::collections::HashMap;
+
+struct Frame {
+    entry: u32,
+    return_address: u64,
+}
+
+#[derive(Default)]
+pub struct Profile {
+    blocks: HashMap<u32, u64>,
+    opcodes: HashMap<u8, u64>,
+    host_calls: HashMap<u64, u64>,
+    stacks: HashMap<Vec<u32>, u64>,
+    frames: Vec<Frame>,
+    block: Option<u32>,
+    suspended: Option<(u64, u64)>,
+}
+
+#[derive(Clone, NifMap)]
+pub struct ProfileMap {
+    pub blocks: HashMap<u32, u64>,
+    pub opcodes: HashMap<u8, u64>,
+    pub host_calls: HashMap<u64, u64>,
+    pub folded: String,
+}
+
+impl Profile {
+    pub fn record(&mut self, step: &Step) {
+        if self.frames.is_empty() {
+            self.frames.push(Frame {
+                entry: step.pc,
+                return_address: 0,
+            });
+        }
+        let block = *self.block.get_or_insert(step.pc);
+
+        *self.blocks.entry(block).or_default() += step.charged;
+        *self.opcodes.entry(step.opcode).or_default() += step.charged;
+        let stack = self.frames.iter().map(|frame| frame.entry).collect();
+        *self.stacks.entry(stack).or_default() += step.charged;
+
+        if opcodes::is_terminator(step.opcode) {
+            self.block = None;
+        }
+        if opcodes::is_call(step.opcode) {
+            self.frames.push(Frame {
+                entry: step.next_pc,
+                return_address: step.after[step.register],
+            });
+        } else if step.opcode == JUMP_IND {
+            let target = step.before[step.register];
+            let returned_from = self
+                .frames
+                .iter()
+                .skip(1)
+                .rposition(|frame| frame.return_address == target);
+            if let Some(depth) = returned_from {
+                self.frames.truncate(depth + 1);
+            }
+        }
+    }
+
+    /// The VM stopped on host call `call_id` with `remaining` gas
+    pub fn suspended(&mut self, call_id: u64, remaining: u64) {
+        self.suspended = Some((call_id, remaining));
+    }
+
+    /// The VM resumes with `remaining` gas; the difference is the host call's
+    pub fn resumed(&mut self, remaining: u64) {
+        if let Some((call_id, before)) = self.suspended.take() {
+            *self.host_calls.entry(call_id).or_default() += before.saturating_sub(remaining);
+        }
+    }
+
+    pub fn to_map(&self) -> ProfileMap {
+        let mut folded: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, gas)| {
+                let frames: Vec<String> = stack.iter().map(u32::to_string).collect();
+                format!("{} {}\n", frames.join(";"), gas)
+            })
+            .collect();
+        folded.sort();
+
+        ProfileMap {
+            blocks: self.blocks.clone(),
+            opcodes: self.opcodes.clone(),
+            host_calls: self.host_calls.clone(),
+            folded: folded.concat(),
+        }
+    }
+}
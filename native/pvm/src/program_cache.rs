@@ -0,0 +1,100 @@
+use pvm_core::VmContext;
+use rustler::NifMap;
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, LazyLock, Mutex,
+};
+
+/// Deblobbed programs kept around; services are few, so a linear scan on eviction is cheap
+const CAPACITY: usize = 64;
+
+pub type CodeHash = [u8; 32];
+
+struct Entry {
+    context: Arc<VmContext>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ProgramCache {
+    entries: HashMap<CodeHash, Entry>,
+    clock: u64,
+}
+
+static PROGRAM_CACHE: LazyLock<Mutex<ProgramCache>> =
+    LazyLock::new(|| Mutex::new(ProgramCache::default()));
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(NifMap)]
+pub struct ProgramCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl ProgramCache {
+    fn get(&mut self, hash: &CodeHash) -> Option<Arc<VmContext>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(hash)?;
+        entry.last_used = self.clock;
+        Some(entry.context.clone())
+    }
+
+    fn insert(&mut self, hash: CodeHash, context: Arc<VmContext>) {
+        if self.entries.len() >= CAPACITY && !self.entries.contains_key(&hash) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(hash, _)| *hash);
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.clock += 1;
+        let last_used = self.clock;
+        self.entries.insert(hash, Entry { context, last_used });
+    }
+}
+
+pub fn code_hash(code: &[u8]) -> CodeHash {
+    let hash = blake2b_simd::Params::new().hash_length(32).hash(code);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+/// The context of the code hashing to `hash`, built by `build` on a miss and
+/// shared with later invocations
+pub fn get_or_build(
+    hash: CodeHash,
+    build: impl FnOnce() -> Option<Arc<VmContext>>,
+) -> Option<Arc<VmContext>> {
+    if let Some(context) = PROGRAM_CACHE.lock().ok()?.get(&hash) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Some(context);
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+
+    // deblob without holding the lock; a concurrent miss just builds it twice
+    let context = build()?;
+    PROGRAM_CACHE.lock().ok()?.insert(hash, context.clone());
+    Some(context)
+}
+
+pub fn stats() -> ProgramCacheStats {
+    let entries = PROGRAM_CACHE
+        .lock()
+        .map(|cache| cache.entries.len())
+        .unwrap_or(0);
+
+    ProgramCacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        entries,
+    }
+}
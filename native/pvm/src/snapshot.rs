@@ -0,0 +1,356 @@
+//! Versioned snapshots of suspended invocations and child VMs
+//!
+//! Layout, integers little endian:
+//!
+//! ```text
+//! "PVMS" | version u8 | kind u8
+//! program: u32 length ++ bytes   (code hash of an invocation, blob of a child VM)
+//! state:   pc u64 | initial_gas u64 | spent_gas u64 | 13 registers u64
+//! runs:    u32 count ++ (first_page u32 | page_count u32 | permission u8)*
+//! pages:   u32 count ++ (page u32 ++ PAGE_SIZE bytes)*
+//! ```
+//!
+//! Runs cover every accessible page with its permission; only pages holding
+//! non-zero bytes are stored. Page permissions and contents are all the state
+//! memory has: without sbrk there is no heap pointer to carry.
+//!
+//! Memory exposes no list of its accessible pages, so only the regions a VM can
+//! have are scanned: the sections of the standard program layout for an
+//! invocation, and the pages given access through the NIFs for a child VM.
+
+use crate::nif_types::{Registers, VmState};
+use pvm_core::{ChildVmInstance, Memory};
+use std::ops::Range;
+
+const MAGIC: &[u8; 4] = b"PVMS";
+const VERSION: u8 = 1;
+
+pub const PAGE_SIZE: usize = 4096;
+const PAGE_COUNT: usize = 1 << 20;
+
+// Z_Z and Z_I in pages
+const ZONE_PAGES: usize = 16;
+const INPUT_PAGES: usize = 4096;
+
+// same encoding as the memory access NIFs
+const NO_ACCESS: u8 = 0;
+const READ: u8 = 1;
+const READ_WRITE: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotKind {
+    Invocation = 0,
+    ChildVm = 1,
+}
+
+/// Page-level view of the memory of an invocation or a child VM
+pub trait PageMemory {
+    fn page_access(&self, page: usize, permission: u8) -> bool;
+    fn read_page(&self, page: usize) -> Option<&[u8]>;
+    fn set_page_access(&mut self, first_page: usize, count: usize, permission: u8) -> bool;
+    fn write_page(&mut self, page: usize, data: &[u8]) -> bool;
+}
+
+impl PageMemory for Memory {
+    fn page_access(&self, page: usize, permission: u8) -> bool {
+        self.check_access(page * PAGE_SIZE, PAGE_SIZE, permission.into())
+    }
+
+    fn read_page(&self, page: usize) -> Option<&[u8]> {
+        self.read(page * PAGE_SIZE, PAGE_SIZE).ok()
+    }
+
+    fn set_page_access(&mut self, first_page: usize, count: usize, permission: u8) -> bool {
+        self.set_access(first_page * PAGE_SIZE, count * PAGE_SIZE, permission.into());
+        true
+    }
+
+    fn write_page(&mut self, page: usize, data: &[u8]) -> bool {
+        self.write(page * PAGE_SIZE, data).is_ok()
+    }
+}
+
+impl PageMemory for ChildVmInstance {
+    fn page_access(&self, page: usize, permission: u8) -> bool {
+        self.check_memory_access(page, 1, permission.into())
+    }
+
+    fn read_page(&self, page: usize) -> Option<&[u8]> {
+        self.read_memory(page * PAGE_SIZE, PAGE_SIZE).ok()
+    }
+
+    fn set_page_access(&mut self, first_page: usize, count: usize, permission: u8) -> bool {
+        self.set_memory_access(first_page, count, permission.into())
+            .is_ok()
+    }
+
+    fn write_page(&mut self, page: usize, data: &[u8]) -> bool {
+        self.write_memory(page * PAGE_SIZE, data).is_ok()
+    }
+}
+
+fn permission_of(memory: &impl PageMemory, page: usize) -> u8 {
+    if memory.page_access(page, READ_WRITE) {
+        READ_WRITE
+    } else if memory.page_access(page, READ) {
+        READ
+    } else {
+        NO_ACCESS
+    }
+}
+
+/// Pages accessible from `start` up to the first inaccessible one before `end`
+fn accessible_from(memory: &impl PageMemory, start: usize, end: usize) -> Range<usize> {
+    let mut page = start;
+    while page < end && memory.page_access(page, READ) {
+        page += 1;
+    }
+    start..page
+}
+
+/// Pages accessible from `end` down to the first inaccessible one after `start`
+fn accessible_until(memory: &impl PageMemory, start: usize, end: usize) -> Range<usize> {
+    let mut page = end;
+    while page > start && memory.page_access(page - 1, READ) {
+        page -= 1;
+    }
+    page..end
+}
+
+/// Accessible pages of an invocation, laid out as by `initialize_program`: the
+/// read-only section from Z_Z, the heap from the next zone after it, the stack
+/// below 2^32 - 2Z_Z - Z_I and the arguments from 2^32 - Z_Z - Z_I. Each section
+/// is contiguous, so it is scanned up to its first inaccessible page.
+pub fn invocation_regions(memory: &impl PageMemory) -> Vec<Range<usize>> {
+    let stack_end = PAGE_COUNT - 2 * ZONE_PAGES - INPUT_PAGES;
+    let arguments_start = PAGE_COUNT - ZONE_PAGES - INPUT_PAGES;
+
+    let read_only = accessible_from(memory, ZONE_PAGES, stack_end);
+    let heap_start = 2 * ZONE_PAGES + read_only.len().div_ceil(ZONE_PAGES) * ZONE_PAGES;
+    let heap = accessible_from(memory, heap_start, stack_end);
+    let stack = accessible_until(memory, heap.end, stack_end);
+    let arguments = accessible_from(memory, arguments_start, PAGE_COUNT);
+
+    vec![read_only, heap, stack, arguments]
+}
+
+/// Add `pages` to sorted, disjoint `regions`, merging it with the ones it touches
+pub fn add_region(regions: &mut Vec<Range<usize>>, pages: Range<usize>) {
+    if pages.is_empty() {
+        return;
+    }
+    let mut merged = pages;
+    regions.retain(|region| {
+        let touches = region.start <= merged.end && merged.start <= region.end;
+        if touches {
+            merged = merged.start.min(region.start)..merged.end.max(region.end);
+        }
+        !touches
+    });
+    let at = regions.partition_point(|region| region.start < merged.start);
+    regions.insert(at, merged);
+}
+
+pub struct Snapshot {
+    pub kind: SnapshotKind,
+    pub program: Vec<u8>,
+    pub state: VmState,
+    runs: Vec<(u32, u32, u8)>,
+    pages: Vec<(u32, Vec<u8>)>,
+}
+
+impl Snapshot {
+    pub fn capture(
+        kind: SnapshotKind,
+        program: &[u8],
+        state: VmState,
+        memory: &impl PageMemory,
+        regions: &[Range<usize>],
+    ) -> Self {
+        let mut runs: Vec<(u32, u32, u8)> = Vec::new();
+        let mut pages = Vec::new();
+
+        let mut regions = regions.to_vec();
+        regions.sort_by_key(|region| region.start);
+        let mut scanned = 0;
+        let region_pages = regions.iter().flat_map(|region| {
+            let pages = region.start.max(scanned)..region.end.min(PAGE_COUNT);
+            scanned = scanned.max(region.end);
+            pages
+        });
+
+        for page in region_pages {
+            let permission = permission_of(memory, page);
+            if permission == NO_ACCESS {
+                continue;
+            }
+
+            match runs.last_mut() {
+                Some((first, count, last))
+                    if *last == permission && (*first + *count) as usize == page =>
+                {
+                    *count += 1
+                }
+                _ => runs.push((page as u32, 1, permission)),
+            }
+
+            if let Some(data) = memory.read_page(page) {
+                if data.iter().any(|&byte| byte != 0) {
+                    pages.push((page as u32, data.to_vec()));
+                }
+            }
+        }
+
+        Self {
+            kind,
+            program: program.to_vec(),
+            state,
+            runs,
+            pages,
+        }
+    }
+
+    /// Pages the snapshot gives access to
+    pub fn regions(&self) -> Vec<Range<usize>> {
+        self.runs
+            .iter()
+            .map(|&(first, count, _)| first as usize..(first + count) as usize)
+            .collect()
+    }
+
+    /// Apply the snapshot's permissions and contents to freshly built memory
+    pub fn restore_memory(&self, memory: &mut impl PageMemory) -> bool {
+        for &(first, count, permission) in &self.runs {
+            if !memory.set_page_access(first as usize, count as usize, permission) {
+                return false;
+            }
+        }
+
+        // read-only pages are made writable just long enough to fill them
+        for (page, data) in &self.pages {
+            let page = *page as usize;
+            let permission = permission_of(memory, page);
+            let filled = memory.set_page_access(page, 1, READ_WRITE)
+                && memory.write_page(page, data)
+                && memory.set_page_access(page, 1, permission);
+            if !filled {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            128 + self.program.len() + self.runs.len() * 9 + self.pages.len() * (4 + PAGE_SIZE),
+        );
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(self.kind as u8);
+
+        out.extend_from_slice(&(self.program.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.program);
+
+        out.extend_from_slice(&(self.state.pc as u64).to_le_bytes());
+        out.extend_from_slice(&self.state.initial_gas.to_le_bytes());
+        out.extend_from_slice(&self.state.spent_gas.to_le_bytes());
+        for register in self.state.registers.data {
+            out.extend_from_slice(&register.to_le_bytes());
+        }
+
+        out.extend_from_slice(&(self.runs.len() as u32).to_le_bytes());
+        for &(first, count, permission) in &self.runs {
+            out.extend_from_slice(&first.to_le_bytes());
+            out.extend_from_slice(&count.to_le_bytes());
+            out.push(permission);
+        }
+
+        out.extend_from_slice(&(self.pages.len() as u32).to_le_bytes());
+        for (page, data) in &self.pages {
+            out.extend_from_slice(&page.to_le_bytes());
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    /// Parse a snapshot of the expected kind, `None` if it is malformed or of another kind
+    pub fn parse(bytes: &[u8], kind: SnapshotKind) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC || reader.u8()? != VERSION || reader.u8()? != kind as u8 {
+            return None;
+        }
+
+        let program_len = reader.u32()? as usize;
+        let program = reader.take(program_len)?.to_vec();
+
+        let pc = usize::try_from(reader.u64()?).ok()?;
+        let initial_gas = reader.u64()?;
+        let spent_gas = reader.u64()?;
+        let mut registers = Registers { data: [0; 13] };
+        for register in registers.data.iter_mut() {
+            *register = reader.u64()?;
+        }
+
+        let mut runs = Vec::new();
+        for _ in 0..reader.u32()? {
+            let (first, count, permission) = (reader.u32()?, reader.u32()?, reader.u8()?);
+            if first as usize + count as usize > PAGE_COUNT
+                || !matches!(permission, READ | READ_WRITE)
+            {
+                return None;
+            }
+            runs.push((first, count, permission));
+        }
+
+        let mut pages = Vec::new();
+        for _ in 0..reader.u32()? {
+            let page = reader.u32()?;
+            if page as usize >= PAGE_COUNT {
+                return None;
+            }
+            pages.push((page, reader.take(PAGE_SIZE)?.to_vec()));
+        }
+
+        if !reader.0.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            kind,
+            program,
+            state: VmState {
+                registers,
+                pc,
+                initial_gas,
+                spent_gas,
+            },
+            runs,
+            pages,
+        })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
@@ -0,0 +1,105 @@
+//! Per-invocation execution traces
+//!
+//! Traced invocations are single-stepped (see `instrumentation`) and every
+//! step is recorded as
+//!
+//! ```text
+//! pc u32 | opcode u8 | gas u64 | changed u8 | (register u8 | value u64)*
+//! ```
+//!
+//! little endian, where `gas` is what remained before the instruction and the
+//! registers are the ones it changed. Steps outside the pc range or opcode
+//! filter are not recorded; past `max_steps` the invocation runs untraced.
+
+use crate::atoms;
+use crate::instrumentation::Step;
+use rustler::{NifResult, Term};
+
+#[derive(Debug, Clone, Default)]
+pub struct TraceOptions {
+    pub max_steps: Option<u64>,
+    pub pc_range: Option<(u32, u32)>,
+    pub opcodes: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct Trace {
+    options: TraceOptions,
+    steps: u64,
+    records: Vec<u8>,
+}
+
+/// The trace requested by an `execute` options map, `None` unless `trace: true`
+pub fn trace_from_options(options: Term) -> NifResult<Option<Trace>> {
+    if !options.is_map() {
+        return Err(rustler::Error::BadArg);
+    }
+    let get = |key| options.map_get(key).ok();
+
+    if !get(atoms::trace()).map_or(Ok(false), |t| t.decode())? {
+        return Ok(None);
+    }
+
+    Ok(Some(Trace::new(TraceOptions {
+        max_steps: get(atoms::max_steps()).map(|t| t.decode()).transpose()?,
+        pc_range: get(atoms::pc_range()).map(|t| t.decode()).transpose()?,
+        opcodes: get(atoms::opcodes()).map(|t| t.decode()).transpose()?,
+    })))
+}
+
+impl Trace {
+    pub fn new(options: TraceOptions) -> Self {
+        Self {
+            options,
+            steps: 0,
+            records: Vec::new(),
+        }
+    }
+
+    /// Whether `max_steps` have been recorded
+    pub fn exhausted(&self) -> bool {
+        self.options
+            .max_steps
+            .is_some_and(|max_steps| self.steps >= max_steps)
+    }
+
+    fn keeps(&self, pc: u32, opcode: u8) -> bool {
+        let in_range = self
+            .options
+            .pc_range
+            .is_none_or(|(from, to)| (from..to).contains(&pc));
+        let wanted = self
+            .options
+            .opcodes
+            .as_ref()
+            .is_none_or(|opcodes| opcodes.contains(&opcode));
+        in_range && wanted
+    }
+
+    pub fn record(&mut self, step: &Step) {
+        if self.exhausted() {
+            return;
+        }
+        self.steps += 1;
+        if !self.keeps(step.pc, step.opcode) {
+            return;
+        }
+
+        let (before, after) = (&step.before, &step.after);
+        self.records.extend_from_slice(&step.pc.to_le_bytes());
+        self.records.push(step.opcode);
+        self.records.extend_from_slice(&step.gas.to_le_bytes());
+
+        let changed: Vec<usize> = (0..13).filter(|&i| before[i] != after[i]).collect();
+        self.records.push(changed.len() as u8);
+        for i in changed {
+            self.records.push(i as u8);
+            self.records.extend_from_slice(&after[i].to_le_bytes());
+        }
+    }
+
+    /// Records collected since the last call
+    pub fn take_records(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.records)
+    }
+}
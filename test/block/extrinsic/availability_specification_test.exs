@@ -3,7 +3,7 @@ defmodule Block.Extrinsic.AvailabilitySpecificationTest do
   use ExUnit.Case
   import Jamixir.Factory
   import Codec.Encoder
-  alias Util.{Hash, MerkleTree}
+  alias Util.{Collections, Hash, MerkleTree}
   import Util.Hex, only: [b16: 1]
 
   setup do
@@ -36,11 +36,8 @@ defmodule Block.Extrinsic.AvailabilitySpecificationTest do
   end
 
   describe "from_execution/3" do
-    import Codec.Encoder
-
     test "calculates erasure root" do
-      erasure_coded_piece = 1
-      segments = [t(erasure_coded_piece), t(erasure_coded_piece)]
+      segments = generate_hash_chain_segments(2)
 
       bundle_binary = <<1, 2, 3, 4, 5, 6, 7, 8, 9, 10>>
       exp_erasure_root = AS.calculate_erasure_root(bundle_binary, segments)
@@ -70,6 +67,28 @@ defmodule Block.Extrinsic.AvailabilitySpecificationTest do
     end
   end
 
+  describe "calculate_erasure_root/2" do
+    # u = MB ([x ∣ x <− T[b♣,s♣]]) from the Elixir building blocks
+    defp reference_erasure_root(bundle_binary, exports) do
+      MerkleTree.well_balanced_merkle_root(
+        for x <- Utils.transpose([AS.b_clubs(bundle_binary), AS.s_clubs(exports)]),
+            do: Collections.union_bin(x)
+      )
+    end
+
+    test "matches the Elixir erasure root" do
+      bundle_binary = :crypto.strong_rand_bytes(3 * Constants.erasure_coded_piece_size() + 5)
+
+      for n <- [0, 1, 3] do
+        segments = generate_hash_chain_segments(n)
+
+        assert AS.calculate_erasure_root(bundle_binary, segments) ==
+                 reference_erasure_root(bundle_binary, segments),
+               "#{n} exports"
+      end
+    end
+  end
+
   describe "b_clubs/1" do
     test "calculates b_clubs correctly for small bundle size" do
       bundle_binary = <<0x1421199ADDAC7C87873A::80>>
@@ -112,6 +112,19 @@ defmodule Block.Extrinsic.TicketProofTest do
                )
     end
 
+    test "construct_n/3 rejects a batch holding one invalid proof", %{
+      state: state,
+      tickets: [t1, t2 | _]
+    } do
+      <<first_byte, rest::binary>> = t2.signature
+      invalid = %TicketProof{t2 | signature: <<first_byte + 1>> <> rest}
+      %{entropy_pool: %{n2: n2}, safrole: %{epoch_root: epoch_root}} = state
+
+      assert {:ok, [_, _]} = TicketProof.construct_n([t1, t2], n2, epoch_root)
+      assert {:error, :bad_ticket_proof} = TicketProof.construct_n([t1, invalid], n2, epoch_root)
+      assert {:error, :bad_ticket_proof} = TicketProof.construct_n([t1], n2, <<1, 2, 3>>)
+    end
+
     test "fails with non-unique tickets", %{state: state, key_pairs: key_pairs} do
       ticket = create_valid_tickets(1, state, key_pairs) |> List.first()
 
@@ -4,7 +4,7 @@ defmodule ErasureCodingTest do
   import Util.Hex, only: [b16: 1]
 
   describe "encode/1" do
-    core_count = %{"tiny" => 2, "full" => 342}
+    params = %{"tiny" => {2, 6, 2}, "full" => {342, 1023, 2}}
 
     for type <- ["tiny", "full"] do
       for size <- ["3", "32", "100", "4096", "4104", "10000"] do
@@ -16,24 +16,25 @@ defmodule ErasureCodingTest do
           |> JsonDecoder.from_json()
 
         @tag test_case: test_case
-        @tag cores: core_count[type]
-        test "test encode decode #{file_name}", %{test_case: test_case, cores: cores} do
+        @tag params: params[type]
+        test "test encode decode #{file_name}", %{test_case: test_case, params: params} do
           bin = test_case["data"]
-          encoded = ErasureCoding.encode(bin, cores)
+          encoded = ErasureCoding.encode(bin, params)
           assert encoded == test_case["shards"]
         end
 
         @tag test_case: test_case
-        @tag cores: core_count[type]
-        test "test decode #{file_name}", %{test_case: test_case, cores: cores} do
+        @tag params: params[type]
+        test "test decode #{file_name}", %{test_case: test_case, params: params} do
           bin = test_case["data"]
           shards = test_case["shards"]
 
           # take random C indices from the V available shards
-          indices = Enum.take_random(0..(length(shards) - 1), cores)
+          {original_shards, _, _} = params
+          indices = Enum.take_random(0..(length(shards) - 1), original_shards)
           selected_shards = Enum.map(indices, &Enum.at(shards, &1))
 
-          decoded = ErasureCoding.decode(selected_shards, indices, byte_size(bin), cores)
+          decoded = ErasureCoding.decode(selected_shards, indices, byte_size(bin), params)
           assert decoded == bin
         end
       end
@@ -42,7 +43,7 @@ defmodule ErasureCodingTest do
     # https://github.com/jam-duna/jamtestnet/issues/139
     @tag :skip
     test "encode wp bundle" do
-      result = ErasureCoding.encode(<<0x1421199ADDAC7C87873A0000::96>>, 2)
+      result = ErasureCoding.encode(<<0x1421199ADDAC7C87873A0000::96>>, {2, 6, 2})
 
       b16result = for b <- result, do: b16(b)
 
@@ -68,22 +69,226 @@ defmodule ErasureCodingTest do
       bin = test_case["data"] |> Base.decode16!(case: :lower)
       expected_shards = test_case["shards"] |> Enum.map(&Base.decode16!(&1, case: :lower))
 
-      assert ErasureCoding.encode(bin, 2) == expected_shards
+      assert ErasureCoding.encode(bin, {2, 6, 2}) == expected_shards
     end
   end
 
   describe "decode/2" do
     test "smoke decode" do
       string = <<1, 2, 3, 4, 5, 6, 7, 8, 9, 10>>
-      [s0, s1, s2, s3, s4, s5] = ErasureCoding.encode(string, 2)
+      [s0, s1, s2, s3, s4, s5] = ErasureCoding.encode(string, {2, 6, 2})
 
-      assert ErasureCoding.decode([s0, s3], [0, 3], 10, 2) == string
-      assert ErasureCoding.decode([s1, s2], [1, 2], 10, 2) == string
-      assert ErasureCoding.decode([s4, s5], [4, 5], 10, 2) == string
+      assert ErasureCoding.decode([s0, s3], [0, 3], 10, {2, 6, 2}) == string
+      assert ErasureCoding.decode([s1, s2], [1, 2], 10, {2, 6, 2}) == string
+      assert ErasureCoding.decode([s4, s5], [4, 5], 10, {2, 6, 2}) == string
       # wrong indices should not match
-      assert ErasureCoding.decode([s4, s5], [1, 2], 10, 2) != string
+      assert ErasureCoding.decode([s4, s5], [1, 2], 10, {2, 6, 2}) != string
       # not enough shards
-      assert ErasureCoding.decode([s1], [1], 10, 2) == :error
+      assert ErasureCoding.decode([s1], [1], 10, {2, 6, 2}) == :too_few_shards
+    end
+
+    test "thread count does not change the shards" do
+      on_exit(fn -> ErasureCoding.set_threads(0) end)
+      bin = :crypto.strong_rand_bytes(100_000)
+
+      assert ErasureCoding.set_threads(1) == :ok
+      sequential = ErasureCoding.encode(bin, {2, 6, 2})
+
+      assert ErasureCoding.set_threads(4) == :ok
+      assert ErasureCoding.encode(bin, {2, 6, 2}) == sequential
+      assert ErasureCoding.decode(Enum.slice(sequential, 3, 2), [3, 4], 100_000, {2, 6, 2}) == bin
+    end
+
+    test "shards share one binary" do
+      shards = ErasureCoding.encode(:crypto.strong_rand_bytes(1_000), {2, 6, 2})
+
+      for shard <- shards do
+        assert byte_size(shard) == 500
+        assert :binary.referenced_byte_size(shard) == 6 * 500
+      end
+
+      assert ErasureCoding.decode(Enum.take(shards, 2), [0, 1], 999, {2, 6, 2}) ==
+               binary_part(Enum.join(Enum.take(shards, 2)), 0, 999)
+    end
+
+    test "malformed shard sets return distinct errors" do
+      [s0, s1, _, s3 | _] = ErasureCoding.encode(<<1, 2, 3, 4, 5, 6, 7, 8, 9, 10>>, {2, 6, 2})
+
+      assert ErasureCoding.decode([s0, s1], [0], 10, {2, 6, 2}) == :mismatched_counts
+      assert ErasureCoding.decode([s0, s1], [0, 6], 10, {2, 6, 2}) == :index_out_of_range
+      assert ErasureCoding.decode([s3, s3], [3, 3], 10, {2, 6, 2}) == :duplicate_index
+
+      assert ErasureCoding.decode([s0, binary_part(s3, 0, 2)], [0, 3], 10, {2, 6, 2}) ==
+               :wrong_shard_length
+    end
+
+    test "rejects inconsistent params" do
+      assert ErasureCoding.encode(<<1, 2, 3>>, {6, 6, 2}) == :invalid_params
+      assert ErasureCoding.encode(<<1, 2, 3>>, {2, 6, 3}) == :invalid_params
+      assert ErasureCoding.decode([<<1, 2>>], [0], 2, {0, 6, 2}) == :invalid_params
+    end
+
+    test "params/0 follows the configured chainspec" do
+      assert ErasureCoding.params() ==
+               {Constants.erasure_code_recovery_threshold(), Constants.validator_count(), 2}
+    end
+  end
+
+  describe "segments" do
+    @params {2, 6, 2}
+
+    test "encode_segments/2 concatenates each validator's segment shards" do
+      segments = for i <- 1..3, do: :binary.copy(<<i>>, 4104)
+      bundles = ErasureCoding.encode_segments(segments, @params)
+
+      per_segment = for s <- segments, do: ErasureCoding.encode(s, @params)
+      expected = for shards <- Enum.zip(per_segment), do: shards |> Tuple.to_list() |> Enum.join()
+
+      assert length(bundles) == 6
+      assert bundles == expected
+    end
+
+    test "decode_segments/4 rebuilds the requested segments" do
+      segments = for i <- 1..3, do: :binary.copy(<<i>>, 4104)
+      bundles = ErasureCoding.encode_segments(segments, @params)
+
+      assert ErasureCoding.decode_segments(Enum.slice(bundles, 3, 2), [3, 4], [2, 0], @params) ==
+               [Enum.at(segments, 2), Enum.at(segments, 0)]
+    end
+
+    test "rejects segments of the wrong size" do
+      assert ErasureCoding.encode_segments([<<1, 2, 3>>], @params) == :wrong_segment_size
+    end
+  end
+
+  describe "erasure root" do
+    alias Block.Extrinsic.AvailabilitySpecification
+    alias Block.Extrinsic.Guarantee.WorkReport
+
+    setup do
+      bundle = :crypto.strong_rand_bytes(100)
+      exports = for i <- 1..2, do: :binary.copy(<<i>>, Constants.segment_size())
+
+      bundle_shards =
+        bundle
+        |> Util.Utils.pad_binary_right(Constants.erasure_coded_piece_size())
+        |> ErasureCoding.encode(ErasureCoding.params())
+
+      segment_bundles =
+        ErasureCoding.encode_segments(
+          exports ++ WorkReport.paged_proofs(exports),
+          ErasureCoding.params()
+        )
+
+      {:ok,
+       bundle: bundle,
+       exports: exports,
+       bundle_shards: bundle_shards,
+       segment_bundles: segment_bundles}
+    end
+
+    test "matches the availability specification", ctx do
+      params = ErasureCoding.params()
+      root = ErasureCoding.erasure_root(ctx.bundle_shards, ctx.segment_bundles, params)
+
+      assert root == AvailabilitySpecification.calculate_erasure_root(ctx.bundle, ctx.exports)
+    end
+
+    test "every validator's shards verify with its justification", ctx do
+      params = ErasureCoding.params()
+
+      {root, justifications} =
+        ErasureCoding.erasure_root_with_justifications(
+          ctx.bundle_shards,
+          ctx.segment_bundles,
+          params
+        )
+
+      for {{b, s, j}, i} <-
+            Enum.with_index(Enum.zip([ctx.bundle_shards, ctx.segment_bundles, justifications])) do
+        assert ErasureCoding.verify_shard(root, i, b, s, j, params)
+        refute ErasureCoding.verify_shard(root, i, b <> <<0>>, s, j, params)
+      end
+    end
+  end
+
+  describe "streaming" do
+    @params {2, 6, 2}
+
+    test "encode_stream/3 matches encode/2" do
+      bin = :crypto.strong_rand_bytes(1_001)
+      chunks = for <<chunk::binary-size(100) <- binary_part(bin, 0, 1_000)>>, do: chunk
+      chunks = chunks ++ [binary_part(bin, 1_000, 1)]
+
+      assert ErasureCoding.encode_stream(chunks, 1_001, @params) ==
+               ErasureCoding.encode(bin, @params)
+    end
+
+    test "encode_stream/3 rejects more or less data than announced" do
+      assert ErasureCoding.encode_stream([<<1, 2, 3>>], 2, @params) == :too_much_data
+      assert ErasureCoding.encode_stream([<<1, 2, 3>>], 4, @params) == :incomplete_data
+    end
+
+    test "drained shard parts add up to the encoded shards" do
+      bin = :crypto.strong_rand_bytes(1_001)
+      encoder = ErasureCoding.encoder_new(1_001, @params)
+
+      parts =
+        for <<chunk::binary-size(91) <- bin>> do
+          assert :ok = ErasureCoding.encoder_push(encoder, chunk)
+          ErasureCoding.encoder_drain(encoder)
+        end
+
+      shards =
+        Enum.zip_with(parts ++ [ErasureCoding.encoder_finish(encoder)], &IO.iodata_to_binary/1)
+
+      assert shards == ErasureCoding.encode(bin, @params)
+    end
+
+    test "finished encoders cannot be reused" do
+      encoder = ErasureCoding.encoder_new(1, @params)
+      assert :ok = ErasureCoding.encoder_push(encoder, <<1>>)
+      assert [_ | _] = ErasureCoding.encoder_finish(encoder)
+      assert ErasureCoding.encoder_finish(encoder) == :finished
+    end
+
+    test "decode_stream/3 stops once enough shards arrived" do
+      bin = :crypto.strong_rand_bytes(100)
+      shards = ErasureCoding.encode(bin, @params) |> Enum.with_index(fn s, i -> {i, s} end)
+
+      # later elements would be duplicates, but they are never consumed
+      stream = Stream.concat(Enum.slice(shards, 4, 2), Stream.cycle(shards))
+
+      assert ErasureCoding.decode_stream(stream, 100, @params) == bin
+      assert ErasureCoding.decode_stream(Enum.take(shards, 1), 100, @params) == :too_few_shards
+    end
+  end
+
+  describe "shard recovery" do
+    @params {2, 6, 2}
+
+    test "reconstruct_shards/5 rebuilds original and recovery shards" do
+      bin = :crypto.strong_rand_bytes(321)
+      shards = ErasureCoding.encode(bin, @params)
+
+      held = Enum.slice(shards, 4, 2)
+
+      assert ErasureCoding.reconstruct_shards(held, [4, 5], [0, 3], 321, @params) ==
+               [Enum.at(shards, 0), Enum.at(shards, 3)]
+
+      assert ErasureCoding.reconstruct_shards(Enum.take(shards, 2), [0, 1], [6], 321, @params) ==
+               :index_out_of_range
+    end
+
+    test "verify_shards/2 detects a tampered shard" do
+      shards = ErasureCoding.encode(:crypto.strong_rand_bytes(321), @params)
+      assert ErasureCoding.verify_shards(shards, @params)
+
+      <<byte, rest::binary>> = Enum.at(shards, 5)
+      tampered = List.replace_at(shards, 5, <<byte + 1, rest::binary>>)
+
+      refute ErasureCoding.verify_shards(tampered, @params)
+      assert ErasureCoding.verify_shards(Enum.drop(shards, 1), @params) == :wrong_shard_count
     end
   end
 end
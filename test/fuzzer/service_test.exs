@@ -137,7 +137,8 @@ defmodule Jamixir.FuzzerTest do
           %{bandersnatch: pub, bandersnatch_priv: priv} =
             JsonDecoder.from_json(JsonReader.read("priv/keys/#{i}.json"))
 
-          {priv, pub}
+          {keypair, ^pub} = RingVrf.generate_secret_from_scalar(:binary.bin_to_list(priv))
+          keypair
         end
 
       # Clear storage from all services
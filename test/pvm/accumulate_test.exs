@@ -74,6 +74,29 @@ defmodule PVM.AccumulateTest do
       # Some gas was consumed
       assert acc_result.gas_used < 1000
     end
+
+    test "returns the trace to the caller when asked", %{accumulation: accumulation, n0_: n0_} do
+      program = <<0, 0, 0, 0, 0>> <> <<op(:ecalli), 0, op(:fallthrough)>>
+      binary = PVM.Helper.init(program, <<5>>)
+      hash = Hash.default(binary)
+
+      service_with_code = %ServiceAccount{
+        balance: 100,
+        code_hash: hash,
+        preimage_storage_p: %{hash => <<0>> <> binary},
+        storage: HashedKeysMap.new(%{{hash, byte_size(binary)} => [0]})
+      }
+
+      accumulation = %{accumulation | services: %{256 => service_with_code}}
+      inputs = [%Operand{data: {:error, :big}}]
+
+      Accumulate.execute(accumulation, 0, 256, 1000, inputs, %{n0_: n0_}, trace: true)
+      assert_receive {:pvm_instrumentation, :accumulate, trace, nil}
+      assert [%{pc: 5} | _] = Pvm.Native.Trace.decode(trace)
+
+      Accumulate.execute(accumulation, 0, 256, 1000, inputs, %{n0_: n0_})
+      refute_receive {:pvm_instrumentation, _, _, _}
+    end
   end
 
   describe "accumulation integration tests" do
@@ -170,6 +170,40 @@ defmodule PVM.ChildVmTest do
     end
   end
 
+  describe "snapshot_child_vm/1 and restore_child_vm/1" do
+    test "a restored child VM has the same state and memory" do
+      registers = Enum.to_list(1..13)
+      {:ok, vm_ref} = Pvm.Native.create_child_vm(halt_program(), 0, 1000, registers)
+
+      addr = min_allowed_address()
+      page_index = div(addr, page_size())
+      assert :ok = Pvm.Native.set_child_vm_memory_access(vm_ref, page_index, 2, 3)
+      assert :ok = Pvm.Native.child_vm_write_memory(vm_ref, addr + 5, <<"snapshot">>)
+      assert :ok = Pvm.Native.set_child_vm_memory_access(vm_ref, page_index + 1, 1, 1)
+
+      assert {:ok, snapshot} = Pvm.Native.snapshot_child_vm(vm_ref)
+      assert {:ok, restored} = Pvm.Native.restore_child_vm(snapshot)
+
+      assert Pvm.Native.get_child_vm_state(restored) == Pvm.Native.get_child_vm_state(vm_ref)
+      assert {:ok, "snapshot"} = Pvm.Native.child_vm_read_memory(restored, addr + 5, 8)
+      assert Pvm.Native.check_child_vm_memory_access(restored, page_index, 1, 3)
+      refute Pvm.Native.check_child_vm_memory_access(restored, page_index + 1, 1, 3)
+      assert Pvm.Native.check_child_vm_memory_access(restored, page_index + 1, 1, 1)
+
+      assert {:ok, ^snapshot} = Pvm.Native.snapshot_child_vm(restored)
+    end
+
+    test "rejects malformed snapshots" do
+      {:ok, vm_ref} = Pvm.Native.create_child_vm(halt_program(), 0, 1000, List.duplicate(0, 13))
+      {:ok, snapshot} = Pvm.Native.snapshot_child_vm(vm_ref)
+
+      assert {:error, :invalid_snapshot} = Pvm.Native.restore_child_vm(<<"PVMS", 1>>)
+      assert {:error, :invalid_snapshot} = Pvm.Native.restore_child_vm(snapshot <> <<0>>)
+      # a child VM snapshot is not an invocation snapshot
+      assert {:error, :invalid_snapshot} = Pvm.Native.restore_invocation(snapshot, <<>>)
+    end
+  end
+
   describe "memory isolation" do
     test "two VM instances have separate memory when permissions are set" do
       program = halt_program()
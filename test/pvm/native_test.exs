@@ -0,0 +1,212 @@
+defmodule Pvm.NativeTest do
+  use ExUnit.Case
+  alias Pvm.Native.{ExecuteResult, Profile, Trace, VmState}
+  use PVM.Instructions
+  import PVM.Constants.HostCallId
+
+  describe "context tokens" do
+    test "programs that fail to load get no token" do
+      assert %ExecuteResult{output: :panic, context_token: nil} =
+               Pvm.Native.execute(<<0xFF, 0xFF, 0xFF>>, 0, 1000, <<>>)
+    end
+
+    test "a finished program's token can no longer be resumed" do
+      bin = PVM.Helper.init_bin(Services.Fibonacci.program())
+      live = Pvm.Native.live_vm_contexts()
+
+      %ExecuteResult{output: output, context_token: token} =
+        Pvm.Native.execute(bin, 0, 10_000, <<>>)
+
+      refute output == :waiting
+      assert is_reference(token)
+      assert Pvm.Native.live_vm_contexts() == live
+
+      state = %VmState{registers: List.duplicate(0, 13), pc: 0, initial_gas: 0, spent_gas: 0}
+
+      assert Pvm.Native.resume(state, Pvm.Native.build_memory(), token) ==
+               {:error, :no_vm_context}
+    end
+  end
+
+  describe "program cache" do
+    test "repeated executions of the same code hit the cache" do
+      bin = PVM.Helper.init_bin(Services.Fibonacci.program())
+      Pvm.Native.execute(bin, 0, 10_000, <<>>)
+      %{hits: hits, misses: misses} = Pvm.Native.program_cache_stats()
+
+      Pvm.Native.execute(bin, 0, 10_000, <<>>)
+
+      assert %{hits: new_hits, misses: ^misses, entries: entries} =
+               Pvm.Native.program_cache_stats()
+
+      assert new_hits == hits + 1
+      assert entries >= 1
+    end
+
+    test "emit_program_cache_stats/0 publishes the counters" do
+      test_pid = self()
+      handler = "program-cache-test"
+
+      :telemetry.attach(
+        handler,
+        [:jamixir, :pvm, :program_cache],
+        fn _event, measurements, _meta, _ -> send(test_pid, {:cache_stats, measurements}) end,
+        nil
+      )
+
+      on_exit(fn -> :telemetry.detach(handler) end)
+
+      Pvm.Native.emit_program_cache_stats()
+      assert_receive {:cache_stats, %{hits: _, misses: _, entries: _}}
+    end
+  end
+
+  describe "snapshot_invocation/3 and restore_invocation/2" do
+    setup do
+      message = "Hello Jamixir PVM"
+
+      # returns the read-only message after a gas host call
+      program =
+        <<op(:load_imm_64), 8, byte_size(message)::64-little, op(:ecalli), host(:gas),
+          op(:fallthrough), op(:load_imm_64), 7, 0x1_0000::64-little, op(:fallthrough)>>
+
+      {:ok, bin: PVM.Helper.init(program, <<128, 44, 1>>, message), message: message}
+    end
+
+    test "a restored invocation resumes like the suspended one", %{bin: bin, message: message} do
+      assert %ExecuteResult{output: :waiting} = Pvm.Native.execute(bin, 0, 1000, <<>>)
+      assert_receive {:ecall, _, state, memory_ref, token}
+
+      assert {:ok, snapshot} = Pvm.Native.snapshot_invocation(state, memory_ref, token)
+      assert {:ok, {^state, restored_memory, restored_token}} =
+               Pvm.Native.restore_invocation(snapshot, bin)
+
+      assert {:ok, ^snapshot} =
+               Pvm.Native.snapshot_invocation(state, restored_memory, restored_token)
+
+      restored = Pvm.Native.resume(state, restored_memory, restored_token)
+      original = Pvm.Native.resume(state, memory_ref, token)
+
+      assert %ExecuteResult{output: ^message} = restored
+      assert restored.output == original.output
+      assert restored.used_gas == original.used_gas
+    end
+
+    test "snapshots only restore with the same program", %{bin: bin} do
+      %ExecuteResult{output: :waiting} = Pvm.Native.execute(bin, 0, 1000, <<>>)
+      assert_receive {:ecall, _, state, memory_ref, token}
+      {:ok, snapshot} = Pvm.Native.snapshot_invocation(state, memory_ref, token)
+
+      other = PVM.Helper.init_bin(Services.Fibonacci.program())
+      assert {:error, :snapshot_mismatch} = Pvm.Native.restore_invocation(snapshot, other)
+    end
+  end
+
+  describe "tracing" do
+    setup do
+      {:ok, bin: PVM.Helper.init_bin(Services.Fibonacci.program())}
+    end
+
+    test "untraced runs return no trace", %{bin: bin} do
+      assert %ExecuteResult{trace: nil} = Pvm.Native.execute(bin, 0, 10_000, <<>>)
+    end
+
+    test "traced runs record every step without changing the result", %{bin: bin} do
+      untraced = Pvm.Native.execute(bin, 0, 10_000, <<>>)
+      traced = Pvm.Native.execute(bin, 0, 10_000, <<>>, %{trace: true})
+
+      assert traced.output == untraced.output
+      assert traced.used_gas == untraced.used_gas
+
+      records = Trace.decode(traced.trace)
+      assert [%{pc: 0, gas: 10_000} | _] = records
+
+      # every step is charged what its instruction cost
+      gas = Enum.map(records, & &1.gas)
+      assert gas == Enum.sort(gas, :desc) and gas == Enum.dedup(gas)
+      assert 10_000 - List.last(gas) < traced.used_gas
+    end
+
+    test "every instruction is a step of its own, terminators included" do
+      # no instruction is free, so none runs in the step of the one before it
+      program =
+        <<op(:load_imm_64), 7, 1::64-little, op(:load_imm_64), 8, 2::64-little,
+          op(:fallthrough), op(:load_imm_64), 9, 3::64-little>>
+
+      bin = PVM.Helper.init(program, <<128, 32, 12, 0>>)
+
+      %ExecuteResult{trace: trace} = Pvm.Native.execute(bin, 0, 1000, <<>>, %{trace: true})
+
+      assert [
+               %{pc: 0, gas: 1000, registers: [{7, 1}]},
+               %{pc: 10, registers: [{8, 2}]},
+               %{pc: 20, registers: []},
+               %{pc: 21, registers: [{9, 3}]} | _
+             ] = Trace.decode(trace)
+    end
+
+    test "max_steps, pc_range and opcodes narrow the trace", %{bin: bin} do
+      %ExecuteResult{trace: trace} =
+        Pvm.Native.execute(bin, 0, 10_000, <<>>, %{trace: true, max_steps: 3})
+
+      assert [_, _, _] = Trace.decode(trace)
+
+      %ExecuteResult{trace: trace} =
+        Pvm.Native.execute(bin, 0, 10_000, <<>>, %{trace: true, pc_range: {0, 1}})
+
+      assert [%{pc: 0}] = Trace.decode(trace)
+
+      %ExecuteResult{trace: trace} =
+        Pvm.Native.execute(bin, 0, 10_000, <<>>, %{trace: true, opcodes: []})
+
+      assert trace == <<>>
+    end
+  end
+
+  describe "profiling" do
+    setup do
+      {:ok, bin: PVM.Helper.init_bin(Services.Fibonacci.program())}
+    end
+
+    test "unprofiled runs return no profile", %{bin: bin} do
+      assert %ExecuteResult{profile: nil} = Pvm.Native.execute(bin, 0, 10_000, <<>>)
+    end
+
+    test "profiled gas adds up to the gas of the untraced run", %{bin: bin} do
+      unprofiled = Pvm.Native.execute(bin, 0, 10_000, <<>>)
+      profiled = Pvm.Native.execute(bin, 0, 10_000, <<>>, %{profile: true})
+
+      assert profiled.output == unprofiled.output
+      assert profiled.used_gas == unprofiled.used_gas
+
+      %{blocks: blocks, opcodes: opcodes, folded: folded} = profiled.profile
+      assert Map.has_key?(blocks, 0)
+      assert Enum.sum(Map.values(blocks)) == unprofiled.used_gas
+      assert Enum.sum(Map.values(opcodes)) == unprofiled.used_gas
+
+      stacks_gas =
+        for line <- String.split(folded, "\n", trim: true),
+            do: line |> String.split(" ") |> List.last() |> String.to_integer()
+
+      assert Enum.sum(stacks_gas) == unprofiled.used_gas
+    end
+
+    @tag :tmp_dir
+    test "folded stacks are written for flamegraphs", %{bin: bin, tmp_dir: tmp_dir} do
+      %ExecuteResult{profile: profile, used_gas: used_gas} =
+        Pvm.Native.execute(bin, 0, 10_000, <<>>, %{profile: true})
+
+      path = Path.join(tmp_dir, "fibonacci.folded")
+      :ok = Profile.write_folded(profile, path)
+
+      gas =
+        for line <- String.split(File.read!(path), "\n", trim: true) do
+          [stack, gas] = String.split(line, " ")
+          assert stack =~ ~r/^0(;\d+)*$/
+          String.to_integer(gas)
+        end
+
+      assert Enum.sum(gas) == used_gas
+    end
+  end
+end
@@ -70,5 +70,11 @@ defmodule System.DataAvailabilityTest do
 
       assert get_segment(root, segment_index) == segment
     end
+
+    test "returns an error when the segment cannot be rebuilt", %{root: root} do
+      expect(NodeStateServerMock, :current_connections, fn -> [] end)
+
+      assert {:error, _} = get_segment(root, 3)
+    end
   end
 end
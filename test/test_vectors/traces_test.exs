@@ -9,17 +9,17 @@ defmodule TracesTest do
   import Util.Hex
   import TestHelper
 
-  setup_all do
-    # uncomment if you want to get trace files
-
-    # System.put_env("PVM_TRACE", "true")
-    :ok
+  setup do
+    # every accumulation of the imported blocks hands its trace back to the test
+    Application.put_env(:jamixir, :pvm_instrumentation, trace: true, max_steps: 1_000)
+    on_exit(fn -> Application.delete_env(:jamixir, :pvm_instrumentation) end)
   end
 
-  def trace_enabled?, do: System.get_env("PVM_TRACE") == "true"
-
   @ignore_fields []
 
+  # modes whose blocks carry work reports to accumulate
+  @accumulating_modes ["storage_light", "preimages_light", "storage", "preimages"]
+
   def traces_path(mode), do: "traces/#{mode}"
   def testnet_path(mode), do: "data/#{mode}/state_transitions"
 
@@ -50,10 +50,6 @@ defmodule TracesTest do
           {failed_blocks, _} =
             for block_number <- block_range, reduce: {[], nil} do
               {failed_blocks, pre_state} ->
-                if trace_enabled?() do
-                  System.put_env("TRACE_NAME", "block-#{block_number}")
-                end
-
                 Logger.info("🧱 Processing block #{block_number}")
                 file = String.pad_leading("#{block_number}", 8, "0")
 
@@ -163,9 +159,27 @@ defmodule TracesTest do
             end
 
           assert failed_blocks == []
+
+          traces = received_traces([])
+          if mode in @accumulating_modes, do: assert(traces != [])
+
+          for trace <- traces do
+            gas = for record <- Pvm.Native.Trace.decode(trace), do: record.gas
+            assert gas == Enum.sort(gas, :desc)
+          end
+
           Logger.info("🎉 All blocks and states are correct")
         end
       end
     end
   end
+
+  defp received_traces(traces) do
+    receive do
+      {:pvm_instrumentation, :accumulate, trace, _} when is_binary(trace) ->
+        received_traces([trace | traces])
+    after
+      0 -> Enum.reverse(traces)
+    end
+  end
 end
@@ -36,6 +36,25 @@ defmodule Util.Crypto.Ed25519ConformanceTest do
              "Basic Ed25519 signature verification should work"
     end
 
+    test "native keys and signatures match :crypto" do
+      seed = :crypto.strong_rand_bytes(32)
+      {pub, ^seed} = :crypto.generate_key(:eddsa, :ed25519, seed)
+      {handle, ^pub} = Ed25519Zip215.keypair_from_seed(seed)
+
+      assert Ed25519Zip215.public_key(handle) == pub
+
+      expected = :crypto.sign(:eddsa, :none, "message", [seed, :ed25519])
+      assert Ed25519Zip215.sign(handle, "message") == expected
+      assert Ed25519Zip215.sign(seed, "message") == expected
+      assert Crypto.valid_signature?(expected, "message", pub)
+    end
+
+    test "rejects seeds of the wrong length" do
+      assert {:error, :invalid_seed_length} = Ed25519Zip215.keypair_from_seed(<<1, 2, 3>>)
+      assert {:error, :invalid_seed_length} = Ed25519Zip215.sign(<<1, 2, 3>>, "message")
+      assert_raise ArgumentError, fn -> Crypto.sign("message", <<1, 2, 3>>) end
+    end
+
     test "test with small-order point (canonical)" do
       pk = <<1>> <> <<0::248>>
       r = <<1>> <> <<0::248>>
@@ -90,6 +109,26 @@ defmodule Util.Crypto.Ed25519ConformanceTest do
       signatures = [{signature, "different message", pub} | signatures]
       assert Ed25519Zip215.batch_verify(signatures) == :error
     end
+
+    test "batch_verify_detailed/1 returns :ok for a valid batch", %{signatures: signatures} do
+      assert Ed25519Zip215.batch_verify_detailed(signatures) == :ok
+      assert Ed25519Zip215.batch_verify_detailed([]) == :ok
+    end
+
+    test "batch_verify_detailed/1 reports failing indices", %{signatures: signatures} do
+      [{sig0, _, pub0}, second, {sig2, msg2, pub2} | rest] = signatures
+
+      signatures = [
+        {sig0, "different message", pub0},
+        second,
+        {binary_part(sig2, 0, 63), msg2, pub2},
+        {sig2, msg2, <<pub2::binary, 0>>} | rest
+      ]
+
+      assert Ed25519Zip215.batch_verify_detailed(signatures) ==
+               {:error,
+                [{0, :error}, {2, :invalid_signature_length}, {3, :invalid_public_key_length}]}
+    end
   end
 
   defp load_test_vectors do
@@ -1,7 +1,7 @@
 defmodule Util.CryptoTest do
   use ExUnit.Case
   alias Util.Crypto
-  import Util.Hex, only: [b16: 1]
+  import Util.Hex, only: [b16: 1, decode16!: 1]
 
   describe "create_ed25519_key_pair/1" do
     test "correct alice vector" do
@@ -24,10 +24,16 @@ defmodule Util.CryptoTest do
 
   describe "create_bandersnatch_key_pair/1" do
     test "correct alice vector" do
-      {priv, pub} = Crypto.create_bandersnatch_key_pair(<<0::256>>)
+      {handle, pub} = keypair = Crypto.create_bandersnatch_key_pair(<<0::256>>)
 
+      assert is_reference(handle)
       assert b16(pub) == "0xff71c6c03ff88adb5ed52c9681de1629a54e702fc14729f6b50d2f0a76f185b3"
-      assert b16(priv) == "0x6137e585dec6e1cd7401ffc8bdfe1400f835a7ddae589ce0ed7b3054e00c9e00"
+
+      # the secret is opaque, check it behaves as the known alice scalar
+      priv = decode16!("0x6137e585dec6e1cd7401ffc8bdfe1400f835a7ddae589ce0ed7b3054e00c9e00")
+      {alice, ^pub} = RingVrf.generate_secret_from_scalar(:binary.bin_to_list(priv))
+      assert RingVrf.ietf_vrf_output(keypair, "context") ==
+               RingVrf.ietf_vrf_output(alice, "context")
     end
   end
 end
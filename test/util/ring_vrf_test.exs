@@ -57,6 +57,91 @@ defmodule RingVrfTest do
     end
   end
 
+  describe "ring_vrf_verify_batch" do
+    test "returns per-item results in order" do
+      {keys, secret} = init_ring_context_and_gen_keys(4)
+      commitment = RingVrf.create_commitment(keys)
+
+      {sig1, out1} = RingVrf.ring_vrf_sign(keys, secret, 0, "context1", "message")
+      {sig2, out2} = RingVrf.ring_vrf_sign(keys, secret, 0, "context2", "message")
+
+      results =
+        RingVrf.ring_vrf_verify_batch(commitment, [
+          {"context1", "message", sig1},
+          {"context1", "message", sig2},
+          {"context1", "message", <<1, 2, 3>>},
+          {"context2", "message", sig2}
+        ])
+
+      assert [
+               {:ok, ^out1},
+               {:error, :verification_failed},
+               {:error, :invalid_signature},
+               {:ok, ^out2}
+             ] = results
+    end
+  end
+
+  describe "multiple ring sizes" do
+    test "signs and verifies with a ring context of another size" do
+      ring_size = 300
+      assert :ok = RingVrf.init_ring_context(ring_size)
+      assert :ok = RingVrf.init_ring_context(ring_size)
+
+      {keys, secret} = init_ring_context_and_gen_keys(4)
+      commitment = RingVrf.create_commitment(keys, ring_size)
+
+      {signature, output} =
+        RingVrf.ring_vrf_sign(keys, secret, 0, "context", "message", ring_size)
+
+      assert {:ok, ^output} =
+               RingVrf.ring_vrf_verify(commitment, "context", "message", signature, ring_size)
+    end
+  end
+
+  describe "invalid public keys" do
+    test "create_commitment rejects an invalid key and names it" do
+      {keys, _secret} = init_ring_context_and_gen_keys(3)
+      bad_key = <<0::256>>
+
+      assert {:error, {:invalid_public_key, ^bad_key}} =
+               RingVrf.create_commitment(List.replace_at(keys, 1, bad_key))
+    end
+
+    test "nil marks a padding key explicitly" do
+      {keys, secret} = init_ring_context_and_gen_keys(3)
+      ring = List.replace_at(keys, 1, nil)
+      commitment = RingVrf.create_commitment(ring)
+
+      assert commitment == RingVrf.cached_commitment(List.replace_at(keys, 1, <<0::256>>))
+
+      {signature, output} = RingVrf.ring_vrf_sign(ring, secret, 0, "context", "message")
+      assert {:ok, ^output} = RingVrf.ring_vrf_verify(commitment, "context", "message", signature)
+    end
+
+    test "only the null key is padded, other invalid keys are errors" do
+      {keys, _secret} = init_ring_context_and_gen_keys(3)
+      bad_key = :binary.copy(<<0xFF>>, 32)
+
+      assert {:ok, [k0, nil, k2]} = RingVrf.padded_ring(List.replace_at(keys, 1, <<0::256>>))
+      assert [k0, k2] == [Enum.at(keys, 0), Enum.at(keys, 2)]
+
+      assert {:error, {:invalid_public_key, ^bad_key}} =
+               RingVrf.padded_ring(List.replace_at(keys, 1, bad_key))
+
+      assert {:error, {:invalid_public_key, ^bad_key}} =
+               RingVrf.cached_commitment(List.replace_at(keys, 1, bad_key))
+    end
+
+    test "ietf_vrf_verify rejects an invalid key" do
+      {_keys, secret} = init_ring_context_and_gen_keys(1)
+      {signature, _output} = RingVrf.ietf_vrf_sign(secret, "context", "message")
+
+      assert {:error, {:invalid_public_key, <<0::256>>}} =
+               RingVrf.ietf_vrf_verify(<<0::256>>, "context", "message", signature)
+    end
+  end
+
   describe "failure scenarios" do
     test "verification fails with altered message" do
       {keys, secret} = init_ring_context_and_gen_keys(2)
@@ -91,6 +176,14 @@ defmodule RingVrfTest do
                RingVrf.ring_vrf_verify(commitment, "context", "message", signature)
     end
 
+    test "verification fails with a malformed commitment" do
+      {keys, secret} = init_ring_context_and_gen_keys(2)
+      {signature, _output} = RingVrf.ring_vrf_sign(keys, secret, 0, "context", "message")
+
+      assert {:error, :invalid_commitment} =
+               RingVrf.ring_vrf_verify(<<1, 2, 3>>, "context", "message", signature)
+    end
+
     test "verification fails with altered auxiliary data" do
       {keys, secret} = init_ring_context_and_gen_keys(2)
       commitment = RingVrf.create_commitment(keys)
@@ -146,12 +239,37 @@ defmodule RingVrfTest do
       %{bandersnatch: pub, bandersnatch_priv: priv} =
         JsonDecoder.from_json(JsonReader.read("priv/alice.json"))
 
-      keypair = {priv, pub}
+      {keypair, ^pub} = RingVrf.generate_secret_from_scalar(:binary.bin_to_list(priv))
       {signature, output} = RingVrf.ietf_vrf_sign(keypair, "context", "message")
       {:ok, ^output} = RingVrf.ietf_vrf_verify(pub, "context", "message", signature)
     end
   end
 
+  describe "ietf_vrf_verify_batch" do
+    test "returns per-item results in order" do
+      {[k1, k2 | _], secret} = init_ring_context_and_gen_keys(3)
+      {sig1, out1} = RingVrf.ietf_vrf_sign(secret, "context1", "message")
+      {sig2, out2} = RingVrf.ietf_vrf_sign(secret, "context2", <<>>)
+
+      results =
+        RingVrf.ietf_vrf_verify_batch([
+          {k1, "context1", "message", sig1},
+          {k2, "context1", "message", sig1},
+          {<<0::256>>, "context1", "message", sig1},
+          {k1, "context2", <<>>, <<1, 2, 3>>},
+          {k1, "context2", <<>>, sig2}
+        ])
+
+      assert [
+               {:ok, ^out1},
+               {:error, :verification_failed},
+               {:error, {:invalid_public_key, <<0::256>>}},
+               {:error, :invalid_signature},
+               {:ok, ^out2}
+             ] = results
+    end
+  end
+
   describe "ietf_vrf error scenarios" do
     test "verification fails with invalid signature" do
       {[key | _], _secret} = init_ring_context_and_gen_keys(3)
@@ -209,6 +327,32 @@ defmodule RingVrfTest do
     end
   end
 
+  describe "vrf outputs without proofs" do
+    test "vrf_output matches ring and ietf signature outputs" do
+      {keys, secret} = init_ring_context_and_gen_keys(3)
+      {ring_sig, ring_out} = RingVrf.ring_vrf_sign(keys, secret, 0, "context", "message")
+      {ietf_sig, ietf_out} = RingVrf.ietf_vrf_sign(secret, "context", "message")
+
+      assert RingVrf.ring_vrf_output(keys, secret, 0, "context") == ring_out
+      assert RingVrf.ietf_vrf_output(secret, "context") == ietf_out
+      assert ring_out == ietf_out
+
+      assert RingVrf.vrf_signature_output(ring_sig) == ring_out
+      assert RingVrf.vrf_signature_output(ietf_sig) == ietf_out
+    end
+
+    test "vrf_signature_output rejects a malformed signature" do
+      assert {:error, :invalid_signature} = RingVrf.vrf_signature_output(<<1, 2, 3>>)
+    end
+  end
+
+  describe "secret handles" do
+    test "keypair holds an opaque handle instead of the scalar" do
+      {{handle, public}, public} = RingVrf.generate_secret_from_rand()
+      assert is_reference(handle)
+    end
+  end
+
   describe "sign with test keys" do
     test "sign with test" do
       {:ok, keys} = KeyManager.load_keys("priv/keys/0.json")